@@ -4,6 +4,13 @@ mod m20240101_000001_create_users_table;
 mod m20240101_000002_create_sessions_table;
 mod m20240101_000003_create_accounts_table;
 mod m20240101_000004_create_verifications_table;
+mod m20240101_000005_create_auth_notify_triggers;
+mod m20240101_000006_add_role_to_users;
+mod m20240101_000007_create_user_withdrawals_table;
+mod m20240101_000008_create_admin_audit_logs_table;
+mod m20240101_000009_add_ban_to_users;
+mod m20240101_000010_add_impersonated_by_to_sessions;
+mod m20240101_000011_create_auth_daily_stats_view;
 
 pub struct Migrator;
 
@@ -15,6 +22,13 @@ impl MigratorTrait for Migrator {
             Box::new(m20240101_000002_create_sessions_table::Migration),
             Box::new(m20240101_000003_create_accounts_table::Migration),
             Box::new(m20240101_000004_create_verifications_table::Migration),
+            Box::new(m20240101_000005_create_auth_notify_triggers::Migration),
+            Box::new(m20240101_000006_add_role_to_users::Migration),
+            Box::new(m20240101_000007_create_user_withdrawals_table::Migration),
+            Box::new(m20240101_000008_create_admin_audit_logs_table::Migration),
+            Box::new(m20240101_000009_add_ban_to_users::Migration),
+            Box::new(m20240101_000010_add_impersonated_by_to_sessions::Migration),
+            Box::new(m20240101_000011_create_auth_daily_stats_view::Migration),
         ]
     }
 }
@@ -0,0 +1,86 @@
+use sea_orm_migration::prelude::*;
+
+/// バックエンドのセッションキャッシュを無効化するための NOTIFY チャンネル名
+const CHANNEL: &str = "auth_cache_invalidation";
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        // sessions の削除・更新時にトークンの SHA-256 を通知
+        // （トークン自体は通知に載せない）
+        db.execute_unprepared(&format!(
+            r#"
+            CREATE OR REPLACE FUNCTION notify_session_invalidation() RETURNS trigger AS $$
+            BEGIN
+                PERFORM pg_notify(
+                    '{CHANNEL}',
+                    json_build_object(
+                        'kind', 'session',
+                        'key', encode(sha256(convert_to(OLD.token, 'UTF8')), 'hex')
+                    )::text
+                );
+                RETURN NULL;
+            END;
+            $$ LANGUAGE plpgsql;
+            "#
+        ))
+        .await?;
+
+        db.execute_unprepared(
+            r#"
+            CREATE TRIGGER trg_sessions_notify_invalidation
+                AFTER UPDATE OR DELETE ON sessions
+                FOR EACH ROW EXECUTE FUNCTION notify_session_invalidation();
+            "#,
+        )
+        .await?;
+
+        // users の更新（退会・プロフィール変更）・削除時にユーザー ID を通知
+        db.execute_unprepared(&format!(
+            r#"
+            CREATE OR REPLACE FUNCTION notify_user_invalidation() RETURNS trigger AS $$
+            BEGIN
+                PERFORM pg_notify(
+                    '{CHANNEL}',
+                    json_build_object('kind', 'user', 'key', OLD.id)::text
+                );
+                RETURN NULL;
+            END;
+            $$ LANGUAGE plpgsql;
+            "#
+        ))
+        .await?;
+
+        db.execute_unprepared(
+            r#"
+            CREATE TRIGGER trg_users_notify_invalidation
+                AFTER UPDATE OR DELETE ON users
+                FOR EACH ROW EXECUTE FUNCTION notify_user_invalidation();
+            "#,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_unprepared(
+            r#"
+            DROP TRIGGER IF EXISTS trg_users_notify_invalidation ON users;
+            DROP FUNCTION IF EXISTS notify_user_invalidation();
+            DROP TRIGGER IF EXISTS trg_sessions_notify_invalidation ON sessions;
+            DROP FUNCTION IF EXISTS notify_session_invalidation();
+            "#,
+        )
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,38 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+use crate::m20240101_000001_create_users_table::Users;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Better Auth の admin プラグイン互換（カンマ区切りで複数ロール）
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Users::Table)
+                    .add_column_if_not_exists(string_null(UsersRole::Role).default("user"))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Users::Table)
+                    .drop_column(UsersRole::Role)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+/// users テーブルに追加するカラム
+#[derive(DeriveIden)]
+pub enum UsersRole {
+    Role,
+}
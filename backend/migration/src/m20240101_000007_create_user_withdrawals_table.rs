@@ -0,0 +1,71 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+use crate::m20240101_000001_create_users_table::Users;
+
+/// 退会猶予期間中のユーザーの元データ（暗号化して保存）
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(UserWithdrawals::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(UserWithdrawals::UserId)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(string(UserWithdrawals::EmailHash))
+                    .col(text(UserWithdrawals::EncryptedData))
+                    .col(
+                        timestamp_with_time_zone(UserWithdrawals::CreatedAt)
+                            .default(Expr::current_timestamp())
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_user_withdrawals_user_id")
+                            .from(UserWithdrawals::Table, UserWithdrawals::UserId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // インデックス作成
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_user_withdrawals_email_hash")
+                    .table(UserWithdrawals::Table)
+                    .col(UserWithdrawals::EmailHash)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(UserWithdrawals::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum UserWithdrawals {
+    Table,
+    UserId,
+    /// 元のメールアドレスの HMAC（復元リクエストの検索用）
+    EmailHash,
+    /// 元のユーザー情報・アカウント（AES-256-GCM）
+    EncryptedData,
+    CreatedAt,
+}
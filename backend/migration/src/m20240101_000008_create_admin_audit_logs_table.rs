@@ -0,0 +1,79 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+/// 管理者の操作履歴（セッションの強制失効など）
+/// 対象ユーザーの完全削除後も履歴を残すため、users への外部キーは張らない
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(AdminAuditLogs::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(AdminAuditLogs::Id)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(string(AdminAuditLogs::ActorId))
+                    .col(string(AdminAuditLogs::Action))
+                    .col(string_null(AdminAuditLogs::TargetUserId))
+                    .col(json_binary_null(AdminAuditLogs::Details))
+                    .col(
+                        timestamp_with_time_zone(AdminAuditLogs::CreatedAt)
+                            .default(Expr::current_timestamp())
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // インデックス作成
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_admin_audit_logs_target_user_id")
+                    .table(AdminAuditLogs::Table)
+                    .col(AdminAuditLogs::TargetUserId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_admin_audit_logs_created_at")
+                    .table(AdminAuditLogs::Table)
+                    .col(AdminAuditLogs::CreatedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(AdminAuditLogs::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum AdminAuditLogs {
+    Table,
+    Id,
+    /// 操作した管理者のユーザー ID
+    ActorId,
+    /// 操作の種類（"session.revoke" など）
+    Action,
+    /// 操作対象のユーザー ID（システム全体の操作は NULL）
+    TargetUserId,
+    /// 操作の詳細（失効したセッション数など）
+    Details,
+    CreatedAt,
+}
@@ -0,0 +1,46 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+use crate::m20240101_000001_create_users_table::Users;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Better Auth の admin プラグイン互換（banned / banReason / banExpires）
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Users::Table)
+                    .add_column_if_not_exists(boolean_null(UsersBan::Banned).default(false))
+                    .add_column_if_not_exists(text_null(UsersBan::BanReason))
+                    .add_column_if_not_exists(timestamp_with_time_zone_null(UsersBan::BanExpires))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Users::Table)
+                    .drop_column(UsersBan::Banned)
+                    .drop_column(UsersBan::BanReason)
+                    .drop_column(UsersBan::BanExpires)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+/// users テーブルに追加するカラム
+#[derive(DeriveIden)]
+pub enum UsersBan {
+    Banned,
+    /// BAN の理由（ユーザーに表示する）
+    BanReason,
+    /// BAN の期限（NULL は無期限）
+    BanExpires,
+}
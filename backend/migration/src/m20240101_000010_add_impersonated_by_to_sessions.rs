@@ -0,0 +1,38 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+use crate::m20240101_000002_create_sessions_table::Sessions;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Better Auth の admin プラグイン互換（なりすましを開始した管理者のユーザー ID）
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Sessions::Table)
+                    .add_column_if_not_exists(string_null(SessionsImpersonation::ImpersonatedBy))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Sessions::Table)
+                    .drop_column(SessionsImpersonation::ImpersonatedBy)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+/// sessions テーブルに追加するカラム
+#[derive(DeriveIden)]
+pub enum SessionsImpersonation {
+    ImpersonatedBy,
+}
@@ -0,0 +1,183 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+use crate::m20240101_000001_create_users_table::Users;
+
+/// 管理者向け統計（GET /api/admin/stats）の集計
+/// - 登録・退会の履歴（user_events）。完全削除されたユーザーも集計できるよう、
+///   users のトリガーで記録し、users への外部キーは張らない
+/// - users の created_at の範囲検索用インデックス
+///   （deleted_at は m20240101_000001 の idx_users_deleted_at を使う）
+/// - 日別の登録数・メール認証状況・退会数のロールアップ（マテリアライズドビュー）
+///   STATS_ROLLUP_REFRESH_SECONDS を設定した場合のみ、バックエンドが定期的に更新して参照する
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(UserEvents::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(UserEvents::Id)
+                            .big_integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(string(UserEvents::UserId))
+                    .col(string(UserEvents::Kind))
+                    .col(
+                        timestamp_with_time_zone(UserEvents::CreatedAt)
+                            .default(Expr::current_timestamp())
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_user_events_kind_created_at")
+                    .table(UserEvents::Table)
+                    .col(UserEvents::Kind)
+                    .col(UserEvents::CreatedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_users_created_at")
+                    .table(Users::Table)
+                    .col(Users::CreatedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        let db = manager.get_connection();
+
+        // 登録（Better Auth が users に INSERT）と退会（deleted_at の設定）を記録
+        // 復元（deleted_at を NULL に戻す）しても退会の記録は残す
+        db.execute_unprepared(
+            r#"
+            CREATE OR REPLACE FUNCTION record_user_event() RETURNS trigger AS $$
+            BEGIN
+                IF TG_OP = 'INSERT' THEN
+                    INSERT INTO user_events (user_id, kind, created_at)
+                    VALUES (NEW.id, 'signup', NEW.created_at);
+                ELSIF OLD.deleted_at IS NULL AND NEW.deleted_at IS NOT NULL THEN
+                    INSERT INTO user_events (user_id, kind, created_at)
+                    VALUES (NEW.id, 'withdrawal', NEW.deleted_at);
+                END IF;
+                RETURN NULL;
+            END;
+            $$ LANGUAGE plpgsql;
+            "#,
+        )
+        .await?;
+
+        db.execute_unprepared(
+            r#"
+            CREATE TRIGGER trg_users_record_event
+                AFTER INSERT OR UPDATE OF deleted_at ON users
+                FOR EACH ROW EXECUTE FUNCTION record_user_event();
+            "#,
+        )
+        .await?;
+
+        // 既存のユーザーの登録・退会を記録（完全削除済みのユーザーは復元できない）
+        db.execute_unprepared(
+            r#"
+            INSERT INTO user_events (user_id, kind, created_at)
+            SELECT id, 'signup', created_at FROM users
+            UNION ALL
+            SELECT id, 'withdrawal', deleted_at FROM users WHERE deleted_at IS NOT NULL;
+            "#,
+        )
+        .await?;
+
+        // 日付は UTC で区切る
+        // verified / unverified は退会済みを除いた、その日に登録したユーザーの内訳
+        db.execute_unprepared(
+            r#"
+            CREATE MATERIALIZED VIEW auth_daily_stats AS
+            WITH events AS (
+                SELECT
+                    (created_at AT TIME ZONE 'UTC')::date AS day,
+                    count(*) FILTER (WHERE kind = 'signup') AS signups,
+                    count(*) FILTER (WHERE kind = 'withdrawal') AS withdrawals
+                FROM user_events
+                GROUP BY 1
+            ),
+            verification AS (
+                SELECT
+                    (created_at AT TIME ZONE 'UTC')::date AS day,
+                    count(*) FILTER (WHERE email_verified) AS verified,
+                    count(*) FILTER (WHERE NOT email_verified) AS unverified
+                FROM users
+                WHERE deleted_at IS NULL
+                GROUP BY 1
+            )
+            SELECT
+                day,
+                coalesce(e.signups, 0) AS signups,
+                coalesce(v.verified, 0) AS verified,
+                coalesce(v.unverified, 0) AS unverified,
+                coalesce(e.withdrawals, 0) AS withdrawals
+            FROM events e
+            FULL OUTER JOIN verification v USING (day);
+            "#,
+        )
+        .await?;
+
+        // REFRESH MATERIALIZED VIEW CONCURRENTLY には一意インデックスが必要
+        db.execute_unprepared(
+            "CREATE UNIQUE INDEX idx_auth_daily_stats_day ON auth_daily_stats (day);",
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"
+                DROP MATERIALIZED VIEW IF EXISTS auth_daily_stats;
+                DROP TRIGGER IF EXISTS trg_users_record_event ON users;
+                DROP FUNCTION IF EXISTS record_user_event();
+                "#,
+            )
+            .await?;
+
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_users_created_at")
+                    .table(Users::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(UserEvents::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum UserEvents {
+    Table,
+    Id,
+    UserId,
+    /// 種類（"signup" / "withdrawal"）
+    Kind,
+    /// 登録・退会の日時
+    CreatedAt,
+}
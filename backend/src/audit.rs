@@ -0,0 +1,71 @@
+use chrono::Utc;
+use sea_orm::{ActiveModelTrait, ActiveValue::Set, ConnectionTrait, DbErr};
+use serde_json::Value;
+
+use crate::entity::admin_audit_logs;
+
+// ============================================================
+// 管理者の操作履歴（admin_audit_logs）
+// - 操作と同じトランザクションで記録し、操作だけが反映されることがないようにする
+// ============================================================
+
+/// 記録する操作の種類
+#[derive(Clone, Copy, Debug)]
+pub enum AuditAction {
+    /// 指定したセッションを失効
+    SessionRevoke,
+    /// ユーザーの全セッションを失効
+    UserSessionsRevoke,
+    /// 指定日時より前に作成された全ユーザーのセッションを失効
+    SessionsRevokeBefore,
+    /// ユーザーを BAN
+    UserBan,
+    /// BAN を解除
+    UserUnban,
+    /// なりすましを開始
+    ImpersonationStart,
+    /// なりすましを終了（actor はなりすましを開始した管理者）
+    ImpersonationStop,
+}
+
+impl AuditAction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::SessionRevoke => "session.revoke",
+            Self::UserSessionsRevoke => "user.sessions.revoke",
+            Self::SessionsRevokeBefore => "sessions.revoke-before",
+            Self::UserBan => "user.ban",
+            Self::UserUnban => "user.unban",
+            Self::ImpersonationStart => "user.impersonate.start",
+            Self::ImpersonationStop => "user.impersonate.stop",
+        }
+    }
+}
+
+/// 操作履歴を記録
+pub async fn record<C: ConnectionTrait>(
+    db: &C,
+    actor_id: &str,
+    action: AuditAction,
+    target_user_id: Option<&str>,
+    details: Value,
+) -> Result<(), DbErr> {
+    admin_audit_logs::ActiveModel {
+        id: Set(uuid::Uuid::new_v4().to_string()),
+        actor_id: Set(actor_id.to_string()),
+        action: Set(action.as_str().to_string()),
+        target_user_id: Set(target_user_id.map(str::to_string)),
+        details: Set((!details.is_null()).then_some(details)),
+        created_at: Set(Utc::now().fixed_offset()),
+    }
+    .insert(db)
+    .await?;
+
+    tracing::info!(
+        "Admin action: actor={}, action={}, target={}",
+        actor_id,
+        action.as_str(),
+        target_user_id.unwrap_or("-")
+    );
+    Ok(())
+}
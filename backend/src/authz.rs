@@ -0,0 +1,292 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::config::ConfigError;
+use crate::middleware::{authenticate, AuthError, AuthExtension, AuthUser};
+use crate::AppState;
+
+// ============================================================
+// 権限（Better Auth の access control 互換）
+// - statements: リソースごとに定義されたアクションの一覧
+//   例: { "project": ["create", "share", "update", "delete"] }
+// - roles: ロールごとに許可するアクション
+//   例: { "admin": { "project": ["create", "delete"] } }
+// - ユーザーのロールのいずれか1つが、要求された権限をすべて満たせば許可
+// ============================================================
+
+/// リソース → アクションの一覧
+pub type Statements = BTreeMap<String, Vec<String>>;
+
+/// 権限の定義（AUTHZ_CONFIG_PATH の JSON ファイルから読み込む）
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct AccessControl {
+    pub statements: Statements,
+    pub roles: BTreeMap<String, Statements>,
+}
+
+/// 要求する権限
+/// 使い方: Permission::new("project", &["create"])
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct Permission(Statements);
+
+impl Permission {
+    pub fn new(resource: &str, actions: &[&str]) -> Self {
+        let mut statements = Statements::new();
+        statements.insert(
+            resource.to_string(),
+            actions.iter().map(|a| a.to_string()).collect(),
+        );
+        Self(statements)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.values().all(Vec::is_empty)
+    }
+}
+
+impl Default for AccessControl {
+    /// Better Auth の admin プラグインのデフォルト定義
+    fn default() -> Self {
+        let statements: Statements = [
+            (
+                "user",
+                &[
+                    "create",
+                    "list",
+                    "set-role",
+                    "ban",
+                    "impersonate",
+                    "delete",
+                    "set-password",
+                    "get",
+                    "update",
+                ][..],
+            ),
+            ("session", &["list", "revoke", "delete"][..]),
+        ]
+        .into_iter()
+        .map(|(resource, actions)| {
+            (
+                resource.to_string(),
+                actions.iter().map(|a| a.to_string()).collect(),
+            )
+        })
+        .collect();
+
+        let roles = BTreeMap::from([
+            ("admin".to_string(), statements.clone()),
+            ("user".to_string(), Statements::new()),
+        ]);
+
+        Self { statements, roles }
+    }
+}
+
+impl AccessControl {
+    /// AUTHZ_CONFIG_PATH が設定されていればその JSON を、なければデフォルト定義を使う
+    pub fn from_env() -> Result<Self, ConfigError> {
+        match env::var("AUTHZ_CONFIG_PATH") {
+            Ok(path) => Self::from_file(&path),
+            Err(_) => Ok(Self::default()),
+        }
+    }
+
+    fn from_file(path: &str) -> Result<Self, ConfigError> {
+        let invalid = |message: String| ConfigError::new("AUTHZ_CONFIG_PATH", message);
+
+        let json = fs::read_to_string(path)
+            .map_err(|e| invalid(format!("could not be read ({path}): {e}")))?;
+        let access_control: Self = serde_json::from_str(&json)
+            .map_err(|e| invalid(format!("is not a valid definition ({path}): {e}")))?;
+        access_control
+            .validate()
+            .map_err(|e| invalid(format!("is not a valid definition ({path}): {e}")))?;
+        Ok(access_control)
+    }
+
+    /// ロールの定義が statements に存在するリソース・アクションのみを使っているか
+    fn validate(&self) -> Result<(), String> {
+        for (role, statements) in &self.roles {
+            for (resource, actions) in statements {
+                let defined = self
+                    .statements
+                    .get(resource)
+                    .ok_or_else(|| format!("role '{role}' uses unknown resource '{resource}'"))?;
+                if let Some(action) = actions.iter().find(|a| !defined.contains(a)) {
+                    return Err(format!(
+                        "role '{role}' uses unknown action '{resource}:{action}'"
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// ロールが要求された権限をすべて持っているか
+    fn role_allows(&self, role: &str, permission: &Permission) -> bool {
+        let Some(granted) = self.roles.get(role) else {
+            return false;
+        };
+
+        permission.0.iter().all(|(resource, actions)| {
+            granted
+                .get(resource)
+                .is_some_and(|allowed| actions.iter().all(|a| allowed.contains(a)))
+        })
+    }
+
+    /// ユーザーのロールのいずれかが要求された権限を満たすか
+    pub fn has_permission(&self, user: &AuthUser, permission: &Permission) -> bool {
+        user.roles
+            .iter()
+            .any(|role| self.role_allows(role, permission))
+    }
+
+    /// ハンドラから使う権限チェック（不足時は 403）
+    /// 使い方: state.access_control.check(&user, &Permission::new("project", &["create"]))?;
+    pub fn check(&self, user: &AuthUser, permission: &Permission) -> Result<(), AuthError> {
+        if self.has_permission(user, permission) {
+            return Ok(());
+        }
+        let err = AuthError::PermissionDenied;
+        err.log();
+        Err(err)
+    }
+}
+
+/// 指定した権限を要求するミドルウェア
+/// なりすましセッションは、対象ユーザーのロールに権限があっても 403（IMPERSONATION_NOT_ALLOWED）
+/// 使い方: middleware::from_fn_with_state((state, permission), require_permission)
+pub async fn require_permission(
+    State((state, permission)): State<(AppState, Permission)>,
+    request: Request,
+    next: Next,
+) -> Result<Response, AuthError> {
+    let (mut parts, body) = request.into_parts();
+    let auth_user = authenticate(&parts, &state).await?;
+    auth_user.ensure_not_impersonated()?;
+    state.access_control.check(&auth_user, &permission)?;
+
+    parts.extensions.insert(AuthExtension(auth_user));
+    Ok(next.run(Request::from_parts(parts, body)).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn access_control(value: serde_json::Value) -> AccessControl {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn project_access_control() -> AccessControl {
+        access_control(json!({
+            "statements": { "project": ["create", "share", "delete"], "billing": ["read"] },
+            "roles": {
+                "owner": { "project": ["create", "share", "delete"], "billing": ["read"] },
+                "member": { "project": ["create"] },
+            },
+        }))
+    }
+
+    #[test]
+    fn role_allows_only_granted_actions() {
+        let ac = project_access_control();
+
+        assert!(ac.role_allows("owner", &Permission::new("project", &["create", "delete"])));
+        assert!(ac.role_allows("member", &Permission::new("project", &["create"])));
+        // 要求したアクションをすべて持っていなければ拒否
+        assert!(!ac.role_allows("member", &Permission::new("project", &["create", "share"])));
+        // 付与されていないリソース・定義されていないロール
+        assert!(!ac.role_allows("member", &Permission::new("billing", &["read"])));
+        assert!(!ac.role_allows("guest", &Permission::new("project", &["create"])));
+    }
+
+    #[test]
+    fn role_allows_requires_every_resource() {
+        let ac = project_access_control();
+        let permission: Permission =
+            serde_json::from_value(json!({ "project": ["create"], "billing": ["read"] })).unwrap();
+
+        assert!(ac.role_allows("owner", &permission));
+        assert!(!ac.role_allows("member", &permission));
+    }
+
+    #[test]
+    fn default_definition_matches_the_admin_plugin() {
+        let ac = AccessControl::default();
+
+        assert!(ac.validate().is_ok());
+        assert!(ac.role_allows("admin", &Permission::new("user", &["ban", "impersonate"])));
+        assert!(ac.role_allows("admin", &Permission::new("session", &["revoke"])));
+        assert!(!ac.role_allows("user", &Permission::new("user", &["list"])));
+    }
+
+    #[test]
+    fn validate_rejects_undefined_resources_and_actions() {
+        assert!(project_access_control().validate().is_ok());
+
+        let unknown_resource = access_control(json!({
+            "statements": { "project": ["create"] },
+            "roles": { "member": { "invoice": ["create"] } },
+        }));
+        assert_eq!(
+            unknown_resource.validate().unwrap_err(),
+            "role 'member' uses unknown resource 'invoice'"
+        );
+
+        let unknown_action = access_control(json!({
+            "statements": { "project": ["create"] },
+            "roles": { "member": { "project": ["create", "archive"] } },
+        }));
+        assert_eq!(
+            unknown_action.validate().unwrap_err(),
+            "role 'member' uses unknown action 'project:archive'"
+        );
+    }
+
+    #[test]
+    fn from_file_reports_invalid_definitions() {
+        let path = env::temp_dir().join(format!("authz-test-{}.json", uuid::Uuid::new_v4()));
+        let path_str = path.to_str().unwrap();
+
+        let error = AccessControl::from_file(path_str).unwrap_err();
+        assert_eq!(error.var, "AUTHZ_CONFIG_PATH");
+
+        fs::write(&path, "{").unwrap();
+        assert!(AccessControl::from_file(path_str).is_err());
+
+        fs::write(
+            &path,
+            json!({
+                "statements": { "project": ["create"] },
+                "roles": { "member": { "project": ["delete"] } },
+            })
+            .to_string(),
+        )
+        .unwrap();
+        let error = AccessControl::from_file(path_str).unwrap_err();
+        assert!(error.message.contains("project:delete"), "{error}");
+
+        fs::write(
+            &path,
+            serde_json::to_string(&project_access_control()).unwrap(),
+        )
+        .unwrap();
+        let ac = AccessControl::from_file(path_str).unwrap();
+        assert!(ac.role_allows("owner", &Permission::new("billing", &["read"])));
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,243 @@
+use std::io::Cursor;
+
+use axum::http::StatusCode;
+use image::{imageops::FilterType, DynamicImage, ImageDecoder, ImageFormat, ImageReader, Limits};
+
+use crate::error::ApiError;
+
+// ============================================================
+// アバター画像の変換
+// - JPEG / PNG / WebP / GIF を受け付け（形式は中身から判定）
+// - EXIF の向きを反映してから、正方形のサムネイル（PNG）に変換
+// - ピクセルデータから再エンコードするため、EXIF などのメタデータは残らない
+// ============================================================
+
+/// アップロードできる最大サイズ（バイト）
+pub const MAX_UPLOAD_BYTES: usize = 5 * 1024 * 1024;
+
+/// 生成するサムネイルの一辺（px）。先頭を users.image に使う
+pub const THUMBNAIL_SIZES: [u32; 2] = [256, 64];
+
+pub const CONTENT_TYPE: &str = "image/png";
+const EXTENSION: &str = "png";
+
+/// デコードを許可する最大の幅・高さ（px）
+const MAX_DIMENSION: u32 = 8192;
+/// デコード時に確保できる最大メモリ（バイト）
+const MAX_ALLOC: u64 = 256 * 1024 * 1024;
+
+const ALLOWED_FORMATS: [ImageFormat; 4] = [
+    ImageFormat::Jpeg,
+    ImageFormat::Png,
+    ImageFormat::WebP,
+    ImageFormat::Gif,
+];
+
+/// BlobStore の key のプレフィックス（ローカル保存では /uploads で配信する）
+pub const KEY_PREFIX: &str = "avatars";
+
+/// BlobStore に保存する key（同じユーザー・サイズは上書きする）
+pub fn key(user_id: &str, size: u32) -> String {
+    format!("{KEY_PREFIX}/{user_id}/{size}.{EXTENSION}")
+}
+
+pub struct Thumbnail {
+    pub size: u32,
+    pub data: Vec<u8>,
+}
+
+/// 画像をデコードし、THUMBNAIL_SIZES のサムネイルを生成
+/// CPU を占有するため、async な処理からは spawn_blocking で呼ぶこと
+pub fn make_thumbnails(data: &[u8]) -> Result<Vec<Thumbnail>, ApiError> {
+    let image = decode(data)?;
+
+    THUMBNAIL_SIZES
+        .iter()
+        .map(|&size| {
+            let thumbnail = image.resize_to_fill(size, size, FilterType::Lanczos3);
+            let mut data = Vec::new();
+            DynamicImage::ImageRgba8(thumbnail.to_rgba8())
+                .write_to(&mut Cursor::new(&mut data), ImageFormat::Png)
+                .map_err(|e| {
+                    tracing::error!("Failed to encode avatar thumbnail: {}", e);
+                    ApiError::internal("Internal server error")
+                })?;
+            Ok(Thumbnail { size, data })
+        })
+        .collect()
+}
+
+fn decode(data: &[u8]) -> Result<DynamicImage, ApiError> {
+    let unsupported = || {
+        ApiError::new(
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            "UNSUPPORTED_MEDIA_TYPE",
+            "Image must be JPEG, PNG, WebP or GIF",
+        )
+    };
+    let invalid = |e: image::ImageError| ApiError::validation(format!("Invalid image: {e}"));
+
+    let mut reader = ImageReader::new(Cursor::new(data))
+        .with_guessed_format()
+        .map_err(|_| unsupported())?;
+    if !reader
+        .format()
+        .is_some_and(|f| ALLOWED_FORMATS.contains(&f))
+    {
+        return Err(unsupported());
+    }
+
+    // 巨大な画像（解凍爆弾）でメモリを使い切らないよう制限する
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_DIMENSION);
+    limits.max_image_height = Some(MAX_DIMENSION);
+    limits.max_alloc = Some(MAX_ALLOC);
+    reader.limits(limits);
+
+    let mut decoder = reader.into_decoder().map_err(invalid)?;
+    let orientation = decoder.orientation().map_err(invalid)?;
+    let mut image = DynamicImage::from_decoder(decoder).map_err(invalid)?;
+    image.apply_orientation(orientation);
+    Ok(image)
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{Rgb, RgbImage};
+
+    use super::*;
+
+    const RED: Rgb<u8> = Rgb([255, 0, 0]);
+    const BLUE: Rgb<u8> = Rgb([0, 0, 255]);
+
+    /// 左半分が赤、右半分が青の画像
+    fn split_image(width: u32, height: u32) -> RgbImage {
+        RgbImage::from_fn(width, height, |x, _| if x < width / 2 { RED } else { BLUE })
+    }
+
+    fn encode(image: &RgbImage, format: ImageFormat) -> Vec<u8> {
+        let mut data = Vec::new();
+        image.write_to(&mut Cursor::new(&mut data), format).unwrap();
+        data
+    }
+
+    /// JPEG の SOI の直後に EXIF（APP1）を差し込む
+    /// Orientation = 6（時計回りに 90° 回転して表示）と、残ってはいけない文字列を含める
+    fn with_exif(jpeg: &[u8]) -> Vec<u8> {
+        let mut tiff = vec![b'I', b'I', 0x2a, 0x00, 0x08, 0x00, 0x00, 0x00];
+        tiff.extend_from_slice(&[0x01, 0x00]);
+        tiff.extend_from_slice(&[0x12, 0x01, 0x03, 0x00, 0x01, 0x00, 0x00, 0x00]);
+        tiff.extend_from_slice(&[0x06, 0x00, 0x00, 0x00]);
+        tiff.extend_from_slice(&[0x00, 0x00, 0x00, 0x00]);
+        tiff.extend_from_slice(b"SECRET-GPS-LOCATION");
+
+        let mut app1 = b"Exif\0\0".to_vec();
+        app1.extend_from_slice(&tiff);
+        let length = u16::try_from(app1.len() + 2).unwrap();
+
+        let mut data = jpeg[..2].to_vec();
+        data.extend_from_slice(&[0xff, 0xe1]);
+        data.extend_from_slice(&length.to_be_bytes());
+        data.extend_from_slice(&app1);
+        data.extend_from_slice(&jpeg[2..]);
+        data
+    }
+
+    fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+        haystack.windows(needle.len()).any(|w| w == needle)
+    }
+
+    fn is_near(pixel: Rgb<u8>, expected: Rgb<u8>) -> bool {
+        pixel
+            .0
+            .iter()
+            .zip(expected.0)
+            .all(|(&a, b)| a.abs_diff(b) < 64)
+    }
+
+    #[test]
+    fn makes_square_png_thumbnails() {
+        let data = encode(&split_image(300, 150), ImageFormat::Png);
+
+        let thumbnails = make_thumbnails(&data).unwrap();
+
+        assert_eq!(
+            thumbnails.iter().map(|t| t.size).collect::<Vec<_>>(),
+            THUMBNAIL_SIZES
+        );
+        for thumbnail in thumbnails {
+            assert_eq!(
+                image::guess_format(&thumbnail.data).unwrap(),
+                ImageFormat::Png
+            );
+            let image = image::load_from_memory(&thumbnail.data).unwrap();
+            assert_eq!(
+                (image.width(), image.height()),
+                (thumbnail.size, thumbnail.size)
+            );
+        }
+    }
+
+    #[test]
+    fn accepts_every_allowed_format() {
+        let image = split_image(32, 32);
+        for format in ALLOWED_FORMATS {
+            let data = encode(&image, format);
+            assert!(make_thumbnails(&data).is_ok(), "{format:?}");
+        }
+    }
+
+    #[test]
+    fn applies_orientation_and_strips_exif() {
+        let jpeg = encode(&split_image(40, 20), ImageFormat::Jpeg);
+        let data = with_exif(&jpeg);
+        assert!(contains(&data, b"SECRET-GPS-LOCATION"));
+
+        // 90° 回転するので、縦長で上半分が赤・下半分が青になる
+        let image = decode(&data).unwrap();
+        assert_eq!((image.width(), image.height()), (20, 40));
+
+        for thumbnail in make_thumbnails(&data).unwrap() {
+            assert!(!contains(&thumbnail.data, b"Exif"));
+            assert!(!contains(&thumbnail.data, b"SECRET-GPS-LOCATION"));
+
+            let image = image::load_from_memory(&thumbnail.data).unwrap().to_rgb8();
+            let size = thumbnail.size;
+            assert!(is_near(*image.get_pixel(size * 3 / 4, size / 8), RED));
+            assert!(is_near(*image.get_pixel(size / 4, size * 7 / 8), BLUE));
+        }
+    }
+
+    #[test]
+    fn rejects_unsupported_formats() {
+        // BMP・TIFF は形式として判定できるが、許可していない
+        for data in [
+            b"not an image".to_vec(),
+            b"BM\0\0\0\0\0\0\0\0\x36\0\0\0".to_vec(),
+            b"II*\0\x08\0\0\0".to_vec(),
+        ] {
+            let error = make_thumbnails(&data).err().unwrap();
+            assert_eq!(error.status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+            assert_eq!(error.code, "UNSUPPORTED_MEDIA_TYPE");
+        }
+    }
+
+    #[test]
+    fn rejects_broken_images() {
+        let png = encode(&split_image(32, 32), ImageFormat::Png);
+
+        let error = make_thumbnails(&png[..png.len() / 2]).err().unwrap();
+
+        assert_eq!(error.status, StatusCode::BAD_REQUEST);
+        assert_eq!(error.code, "VALIDATION_ERROR");
+    }
+
+    #[test]
+    fn rejects_oversized_dimensions() {
+        let png = encode(&RgbImage::new(MAX_DIMENSION + 1, 1), ImageFormat::Png);
+
+        let error = make_thumbnails(&png).err().unwrap();
+
+        assert_eq!(error.status, StatusCode::BAD_REQUEST);
+    }
+}
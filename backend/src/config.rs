@@ -0,0 +1,357 @@
+use std::env;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// 認証まわりの設定（環境変数から読み込む）
+#[derive(Clone, Debug)]
+pub struct AuthConfig {
+    /// Better Auth と共有する署名用シークレット（BETTER_AUTH_SECRET）
+    pub secret: String,
+    /// Bearer 認証で署名なしのトークンを受け付けるか（BEARER_ALLOW_UNSIGNED_TOKENS、デフォルト false）
+    /// true は安全ではない: DB やログから漏れたトークンが署名なしで使えてしまう
+    /// （Better Auth の bearer プラグインの requireSignature: false に相当）
+    pub bearer_allow_unsigned: bool,
+    /// セッションキャッシュの TTL（SESSION_CACHE_TTL_SECONDS、0 で無効）
+    pub session_cache_ttl: Duration,
+    /// セッションキャッシュの最大件数（SESSION_CACHE_MAX_CAPACITY）
+    pub session_cache_max_capacity: u64,
+    /// セッションの有効期間（SESSION_EXPIRES_IN_SECONDS、Better Auth の session.expiresIn）
+    pub session_expires_in: chrono::Duration,
+    /// 有効期限を延長する間隔（SESSION_UPDATE_AGE_SECONDS、Better Auth の session.updateAge）
+    pub session_update_age: chrono::Duration,
+    /// 有効期限の延長を行わない（SESSION_DISABLE_REFRESH、Better Auth の session.disableSessionRefresh）
+    pub session_disable_refresh: bool,
+    /// セッション Cookie の名前の候補（この順に探す）
+    pub session_cookie_names: Vec<String>,
+    /// Better Auth の dont_remember Cookie の名前の候補（"{cookie_prefix}.dont_remember"）
+    /// 「ログイン状態を保持しない」でログインしたセッションは有効期限を延長しない
+    pub dont_remember_cookie_names: Vec<String>,
+    /// 退会後に復元できる期間。過ぎたユーザーは完全に削除する（WITHDRAWAL_GRACE_PERIOD_DAYS）
+    pub withdrawal_grace_period: chrono::Duration,
+    /// 猶予期間を過ぎたユーザーを削除する間隔（WITHDRAWAL_PURGE_INTERVAL_SECONDS）
+    pub withdrawal_purge_interval: Duration,
+    /// フロントエンドの URL（メール内のリンクに使う。BETTER_AUTH_URL）
+    pub base_url: String,
+    /// なりすまし中に管理者のセッショントークンを退避する Cookie の名前
+    /// （Better Auth の admin プラグインと同じ "{cookie_prefix}.admin_session"）
+    pub admin_session_cookie_name: String,
+    /// なりすましセッションの有効期間
+    /// （IMPERSONATION_SESSION_DURATION_SECONDS、Better Auth の impersonationSessionDuration）
+    pub impersonation_session_duration: chrono::Duration,
+    /// 統計のロールアップ（auth_daily_stats）を更新する間隔
+    /// （STATS_ROLLUP_REFRESH_SECONDS、0 でロールアップを使わず都度集計）
+    pub stats_rollup_refresh_interval: Duration,
+    /// 前段にある信頼できるリバースプロキシの数（TRUSTED_PROXY_HOPS）
+    /// 0 の場合は X-Forwarded-For を使わず、接続元の IP をクライアントの IP とする
+    pub trusted_proxy_hops: usize,
+}
+
+/// 環境変数の値が不正（起動時に変数名とともに報告する）
+#[derive(Debug)]
+pub struct ConfigError {
+    pub var: &'static str,
+    pub message: String,
+}
+
+impl ConfigError {
+    pub(crate) fn new(var: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            var,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.var, self.message)
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// 環境変数を読み込んでパース（未設定ならデフォルト値）
+fn parse_env<T: FromStr>(var: &'static str, default: &str) -> Result<T, ConfigError> {
+    let value = env::var(var).unwrap_or_else(|_| default.into());
+    value
+        .parse()
+        .map_err(|_| ConfigError::new(var, format!("must be a number (got {value:?})")))
+}
+
+/// Better Auth が HTTPS で Cookie 名に付けるプレフィックス
+const SECURE_COOKIE_PREFIX: &str = "__Secure-";
+
+/// "__Secure-" 付きの Cookie 名を使うか（SESSION_COOKIE_SECURE）
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum CookieSecure {
+    /// "true": "__Secure-" 付きのみ
+    Always,
+    /// "false": "__Secure-" なしのみ
+    Never,
+    /// "auto": 両方受け付ける
+    Auto,
+}
+
+impl CookieSecure {
+    fn parse(value: &str) -> Result<Self, ConfigError> {
+        match value {
+            "true" => Ok(Self::Always),
+            "false" => Ok(Self::Never),
+            "auto" => Ok(Self::Auto),
+            other => Err(ConfigError::new(
+                "SESSION_COOKIE_SECURE",
+                format!("must be true, false or auto (got {other:?})"),
+            )),
+        }
+    }
+}
+
+/// セッション Cookie の名前の候補を決定
+///
+/// - name: SESSION_COOKIE_NAME（Better Auth の advanced.cookies.session_token.name）
+///   未設定なら "{cookie_prefix}.session_token"（Better Auth の advanced.cookiePrefix）
+/// - secure: SESSION_COOKIE_SECURE（Better Auth の advanced.useSecureCookies）
+///   - "true": "__Secure-" 付きのみ
+///   - "false": "__Secure-" なしのみ
+///   - "auto"（デフォルト）: 両方受け付ける。BETTER_AUTH_URL が https なら "__Secure-" 付きを優先
+fn session_cookie_names(
+    cookie_prefix: &str,
+    name: Option<&str>,
+    secure: CookieSecure,
+    base_url: Option<&str>,
+) -> Vec<String> {
+    let plain = match name {
+        Some(name) => name.to_string(),
+        None => format!("{cookie_prefix}.session_token"),
+    };
+    let secure_name = format!("{SECURE_COOKIE_PREFIX}{plain}");
+
+    match secure {
+        CookieSecure::Always => vec![secure_name],
+        CookieSecure::Never => vec![plain],
+        CookieSecure::Auto => {
+            if base_url.is_some_and(|url| url.starts_with("https://")) {
+                vec![secure_name, plain]
+            } else {
+                vec![plain, secure_name]
+            }
+        }
+    }
+}
+
+impl AuthConfig {
+    pub fn from_env() -> Result<Self, ConfigError> {
+        let secret = env::var("BETTER_AUTH_SECRET")
+            .map_err(|_| ConfigError::new("BETTER_AUTH_SECRET", "must be set"))?;
+
+        let bearer_allow_unsigned = env::var("BEARER_ALLOW_UNSIGNED_TOKENS")
+            .map(|v| v == "true")
+            .unwrap_or(false);
+        if bearer_allow_unsigned {
+            tracing::warn!(
+                "BEARER_ALLOW_UNSIGNED_TOKENS is true: unsigned session tokens are accepted as Bearer credentials (insecure)"
+            );
+        }
+
+        let session_cache_ttl =
+            parse_env("SESSION_CACHE_TTL_SECONDS", "60").map(Duration::from_secs)?;
+
+        let session_cache_max_capacity = parse_env("SESSION_CACHE_MAX_CAPACITY", "10000")?;
+
+        // Better Auth のデフォルト（7日 / 1日）に合わせる
+        let session_expires_in =
+            parse_env("SESSION_EXPIRES_IN_SECONDS", "604800").map(chrono::Duration::seconds)?;
+
+        let session_update_age =
+            parse_env("SESSION_UPDATE_AGE_SECONDS", "86400").map(chrono::Duration::seconds)?;
+
+        let session_disable_refresh = env::var("SESSION_DISABLE_REFRESH")
+            .map(|v| v == "true")
+            .unwrap_or(false);
+
+        let cookie_prefix =
+            env::var("BETTER_AUTH_COOKIE_PREFIX").unwrap_or_else(|_| "better-auth".into());
+        let cookie_name = env::var("SESSION_COOKIE_NAME").ok();
+        let cookie_secure = CookieSecure::parse(
+            &env::var("SESSION_COOKIE_SECURE").unwrap_or_else(|_| "auto".into()),
+        )?;
+        let base_url = env::var("BETTER_AUTH_URL").ok();
+        let dont_remember_cookie_names = session_cookie_names(
+            &cookie_prefix,
+            Some(&format!("{cookie_prefix}.dont_remember")),
+            cookie_secure,
+            base_url.as_deref(),
+        );
+        let session_cookie_names = session_cookie_names(
+            &cookie_prefix,
+            cookie_name.as_deref(),
+            cookie_secure,
+            base_url.as_deref(),
+        );
+        // セッション Cookie と同じく "__Secure-" の有無を合わせる
+        let admin_session_cookie_name = if session_cookie_names[0].starts_with(SECURE_COOKIE_PREFIX)
+        {
+            format!("{SECURE_COOKIE_PREFIX}{cookie_prefix}.admin_session")
+        } else {
+            format!("{cookie_prefix}.admin_session")
+        };
+        let base_url = base_url
+            .unwrap_or_else(|| "http://localhost:3050".into())
+            .trim_end_matches('/')
+            .to_string();
+
+        // Better Auth のデフォルト（1時間）に合わせる
+        let impersonation_session_duration =
+            parse_env("IMPERSONATION_SESSION_DURATION_SECONDS", "3600")
+                .map(chrono::Duration::seconds)?;
+
+        let withdrawal_grace_period =
+            parse_env("WITHDRAWAL_GRACE_PERIOD_DAYS", "30").map(chrono::Duration::days)?;
+
+        let withdrawal_purge_interval =
+            parse_env("WITHDRAWAL_PURGE_INTERVAL_SECONDS", "3600").map(Duration::from_secs)?;
+
+        let stats_rollup_refresh_interval =
+            parse_env("STATS_ROLLUP_REFRESH_SECONDS", "0").map(Duration::from_secs)?;
+
+        let trusted_proxy_hops = parse_env("TRUSTED_PROXY_HOPS", "0")?;
+
+        Ok(Self {
+            secret,
+            bearer_allow_unsigned,
+            session_cache_ttl,
+            session_cache_max_capacity,
+            session_expires_in,
+            session_update_age,
+            session_disable_refresh,
+            session_cookie_names,
+            dont_remember_cookie_names,
+            withdrawal_grace_period,
+            withdrawal_purge_interval,
+            base_url,
+            admin_session_cookie_name,
+            impersonation_session_duration,
+            stats_rollup_refresh_interval,
+            trusted_proxy_hops,
+        })
+    }
+}
+
+#[cfg(test)]
+impl AuthConfig {
+    /// テスト用の設定（Better Auth のデフォルトと同じ Cookie 名・有効期間）
+    pub(crate) fn for_tests(secret: &str) -> Self {
+        Self {
+            secret: secret.to_string(),
+            bearer_allow_unsigned: false,
+            session_cache_ttl: Duration::ZERO,
+            session_cache_max_capacity: 0,
+            session_expires_in: chrono::Duration::days(7),
+            session_update_age: chrono::Duration::days(1),
+            session_disable_refresh: false,
+            session_cookie_names: session_cookie_names(
+                "better-auth",
+                None,
+                CookieSecure::Auto,
+                None,
+            ),
+            dont_remember_cookie_names: session_cookie_names(
+                "better-auth",
+                Some("better-auth.dont_remember"),
+                CookieSecure::Auto,
+                None,
+            ),
+            withdrawal_grace_period: chrono::Duration::days(30),
+            withdrawal_purge_interval: Duration::ZERO,
+            base_url: "http://localhost:3050".into(),
+            admin_session_cookie_name: "better-auth.admin_session".into(),
+            impersonation_session_duration: chrono::Duration::hours(1),
+            stats_rollup_refresh_interval: Duration::ZERO,
+            trusted_proxy_hops: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HTTP: Option<&str> = Some("http://localhost:3050");
+    const HTTPS: Option<&str> = Some("https://example.com");
+
+    #[test]
+    fn secure_true_accepts_only_secure_cookie() {
+        assert_eq!(
+            session_cookie_names("better-auth", None, CookieSecure::Always, HTTP),
+            ["__Secure-better-auth.session_token"]
+        );
+    }
+
+    #[test]
+    fn secure_false_accepts_only_plain_cookie() {
+        assert_eq!(
+            session_cookie_names("better-auth", None, CookieSecure::Never, HTTPS),
+            ["better-auth.session_token"]
+        );
+    }
+
+    #[test]
+    fn secure_auto_prefers_plain_cookie_over_http() {
+        assert_eq!(
+            session_cookie_names("better-auth", None, CookieSecure::Auto, HTTP),
+            [
+                "better-auth.session_token",
+                "__Secure-better-auth.session_token"
+            ]
+        );
+        assert_eq!(
+            session_cookie_names("better-auth", None, CookieSecure::Auto, None),
+            [
+                "better-auth.session_token",
+                "__Secure-better-auth.session_token"
+            ]
+        );
+    }
+
+    #[test]
+    fn secure_auto_prefers_secure_cookie_over_https() {
+        assert_eq!(
+            session_cookie_names("better-auth", None, CookieSecure::Auto, HTTPS),
+            [
+                "__Secure-better-auth.session_token",
+                "better-auth.session_token"
+            ]
+        );
+    }
+
+    #[test]
+    fn custom_prefix_and_name() {
+        assert_eq!(
+            session_cookie_names("my-app", None, CookieSecure::Auto, HTTPS),
+            ["__Secure-my-app.session_token", "my-app.session_token"]
+        );
+        // SESSION_COOKIE_NAME はプレフィックスより優先
+        assert_eq!(
+            session_cookie_names("my-app", Some("sid"), CookieSecure::Never, HTTP),
+            ["sid"]
+        );
+    }
+
+    #[test]
+    fn parses_cookie_secure() {
+        assert_eq!(CookieSecure::parse("true").unwrap(), CookieSecure::Always);
+        assert_eq!(CookieSecure::parse("false").unwrap(), CookieSecure::Never);
+        assert_eq!(CookieSecure::parse("auto").unwrap(), CookieSecure::Auto);
+    }
+
+    #[test]
+    fn unknown_cookie_secure_is_config_error() {
+        let err = CookieSecure::parse("yes").unwrap_err();
+        assert_eq!(err.var, "SESSION_COOKIE_SECURE");
+        assert_eq!(
+            err.to_string(),
+            r#"SESSION_COOKIE_SECURE must be true, false or auto (got "yes")"#
+        );
+    }
+}
@@ -0,0 +1,166 @@
+use serde::Serialize;
+use woothee::parser::Parser;
+
+// ============================================================
+// User-Agent の解析
+// - sessions.user_agent をブラウザ・OS・端末種別に変換する
+// - ルールは woothee に同梱されたデータセットを使う（外部通信なし）
+// - 判定できない項目は None / DeviceType::Unknown
+// ============================================================
+
+/// woothee が判定できなかった場合の値
+const UNKNOWN: &str = "UNKNOWN";
+
+/// 端末種別
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeviceType {
+    Desktop,
+    Mobile,
+    Tablet,
+    /// ゲーム機・テレビなど
+    Appliance,
+    /// クローラー・ボット
+    Bot,
+    #[default]
+    Unknown,
+}
+
+/// User-Agent から取得した端末情報
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct DeviceInfo {
+    pub browser: Option<String>,
+    pub browser_version: Option<String>,
+    pub os: Option<String>,
+    pub os_version: Option<String>,
+    pub device_type: DeviceType,
+}
+
+impl DeviceInfo {
+    /// User-Agent を解析（未設定・空文字は Unknown）
+    pub fn parse(user_agent: Option<&str>) -> Self {
+        let Some(user_agent) = user_agent.filter(|ua| !ua.trim().is_empty()) else {
+            return Self::default();
+        };
+        let Some(result) = Parser::new().parse(user_agent) else {
+            return Self::default();
+        };
+
+        let device_type = match result.category {
+            "pc" => DeviceType::Desktop,
+            // woothee は iPad / Android タブレットも smartphone に分類するため補正する
+            "smartphone" if is_tablet(result.os, user_agent) => DeviceType::Tablet,
+            "smartphone" | "mobilephone" => DeviceType::Mobile,
+            "appliance" => DeviceType::Appliance,
+            "crawler" => DeviceType::Bot,
+            _ => DeviceType::Unknown,
+        };
+
+        Self {
+            browser: known(result.name),
+            browser_version: known(result.version),
+            os: known(result.os),
+            os_version: known(&result.os_version),
+            device_type,
+        }
+    }
+}
+
+/// Android は "Mobile" を含まない User-Agent がタブレット
+fn is_tablet(os: &str, user_agent: &str) -> bool {
+    os == "iPad" || (os == "Android" && !user_agent.contains("Mobile"))
+}
+
+fn known(value: &str) -> Option<String> {
+    (!value.is_empty() && value != UNKNOWN).then(|| value.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(
+        browser: &str,
+        browser_version: &str,
+        os: &str,
+        os_version: &str,
+        device_type: DeviceType,
+    ) -> DeviceInfo {
+        DeviceInfo {
+            browser: Some(browser.into()),
+            browser_version: Some(browser_version.into()),
+            os: Some(os.into()),
+            os_version: Some(os_version.into()),
+            device_type,
+        }
+    }
+
+    #[test]
+    fn parses_user_agents() {
+        let cases = [
+            (
+                "Chrome (Windows)",
+                "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36",
+                info("Chrome", "120.0.0.0", "Windows 10", "NT 10.0", DeviceType::Desktop),
+            ),
+            (
+                "Safari (iOS)",
+                "Mozilla/5.0 (iPhone; CPU iPhone OS 17_2 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.2 Mobile/15E148 Safari/604.1",
+                info("Safari", "17.2", "iPhone", "17.2", DeviceType::Mobile),
+            ),
+            (
+                "Safari (iPad)",
+                "Mozilla/5.0 (iPad; CPU OS 17_2 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.2 Mobile/15E148 Safari/604.1",
+                info("Safari", "17.2", "iPad", "17.2", DeviceType::Tablet),
+            ),
+            (
+                "Firefox (macOS)",
+                "Mozilla/5.0 (Macintosh; Intel Mac OS X 10.15; rv:121.0) Gecko/20100101 Firefox/121.0",
+                info("Firefox", "121.0", "Mac OSX", "10.15", DeviceType::Desktop),
+            ),
+            (
+                "Edge (Windows)",
+                "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36 Edg/120.0.2210.91",
+                info("Edge", "120.0.2210.91", "Windows 10", "NT 10.0", DeviceType::Desktop),
+            ),
+            (
+                "Chrome (Android phone)",
+                "Mozilla/5.0 (Linux; Android 14; Pixel 8) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.6099.144 Mobile Safari/537.36",
+                info("Chrome", "120.0.6099.144", "Android", "14", DeviceType::Mobile),
+            ),
+            (
+                "Chrome (Android tablet)",
+                "Mozilla/5.0 (Linux; Android 13; SM-X700) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.6099.144 Safari/537.36",
+                info("Chrome", "120.0.6099.144", "Android", "13", DeviceType::Tablet),
+            ),
+            (
+                "Googlebot",
+                "Mozilla/5.0 (compatible; Googlebot/2.1; +http://www.google.com/bot.html)",
+                DeviceInfo {
+                    browser: Some("Googlebot".into()),
+                    device_type: DeviceType::Bot,
+                    ..DeviceInfo::default()
+                },
+            ),
+        ];
+
+        for (name, user_agent, expected) in cases {
+            assert_eq!(DeviceInfo::parse(Some(user_agent)), expected, "{name}");
+        }
+    }
+
+    #[test]
+    fn empty_user_agent_is_unknown() {
+        assert_eq!(DeviceInfo::parse(None), DeviceInfo::default());
+        assert_eq!(DeviceInfo::parse(Some("")), DeviceInfo::default());
+        assert_eq!(DeviceInfo::parse(Some("   ")), DeviceInfo::default());
+    }
+
+    #[test]
+    fn unknown_user_agent_is_unknown() {
+        assert_eq!(
+            DeviceInfo::parse(Some("my-custom-client")),
+            DeviceInfo::default()
+        );
+    }
+}
@@ -1,8 +1,14 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.13
 
+// prelude は sea-orm-codegen の生成物で、アプリからは各モジュールを直接参照している
+#[allow(unused_imports)]
 pub mod prelude;
 
 pub mod accounts;
+pub mod admin_audit_logs;
+pub mod auth_daily_stats;
 pub mod sessions;
+pub mod user_events;
+pub mod user_withdrawals;
 pub mod users;
 pub mod verifications;
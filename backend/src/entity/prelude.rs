@@ -1,6 +1,10 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.13
 
 pub use super::accounts::Entity as Accounts;
+pub use super::admin_audit_logs::Entity as AdminAuditLogs;
+pub use super::auth_daily_stats::Entity as AuthDailyStats;
 pub use super::sessions::Entity as Sessions;
+pub use super::user_events::Entity as UserEvents;
+pub use super::user_withdrawals::Entity as UserWithdrawals;
 pub use super::users::Entity as Users;
 pub use super::verifications::Entity as Verifications;
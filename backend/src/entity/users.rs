@@ -16,6 +16,11 @@ pub struct Model {
     pub created_at: DateTimeWithTimeZone,
     pub updated_at: DateTimeWithTimeZone,
     pub deleted_at: Option<DateTimeWithTimeZone>,
+    pub role: Option<String>,
+    pub banned: Option<bool>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub ban_reason: Option<String>,
+    pub ban_expires: Option<DateTimeWithTimeZone>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -24,6 +29,8 @@ pub enum Relation {
     Accounts,
     #[sea_orm(has_many = "super::sessions::Entity")]
     Sessions,
+    #[sea_orm(has_one = "super::user_withdrawals::Entity")]
+    UserWithdrawals,
 }
 
 impl Related<super::accounts::Entity> for Entity {
@@ -38,4 +45,10 @@ impl Related<super::sessions::Entity> for Entity {
     }
 }
 
+impl Related<super::user_withdrawals::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::UserWithdrawals.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,118 @@
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+
+use crate::export::ExportError;
+use crate::middleware::AuthError;
+
+/// Next.js 側と共通のエラーレスポンス形式
+/// `{"error": {"message": "...", "code": "..."}}`
+#[derive(Serialize)]
+pub struct ErrorResponse {
+    pub error: ErrorBody,
+}
+
+#[derive(Serialize)]
+pub struct ErrorBody {
+    pub message: String,
+    pub code: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<serde_json::Value>,
+}
+
+/// エラーレスポンスを生成
+pub fn error_response(
+    status: StatusCode,
+    code: &'static str,
+    message: impl Into<String>,
+) -> Response {
+    error_response_with_details(status, code, message, None)
+}
+
+/// details 付きのエラーレスポンスを生成
+pub fn error_response_with_details(
+    status: StatusCode,
+    code: &'static str,
+    message: impl Into<String>,
+    details: Option<serde_json::Value>,
+) -> Response {
+    let body = ErrorResponse {
+        error: ErrorBody {
+            message: message.into(),
+            code,
+            details,
+        },
+    };
+    (status, Json(body)).into_response()
+}
+
+/// ハンドラ用のエラー型
+/// 使い方: async fn handler(...) -> Result<Json<T>, ApiError>
+#[derive(Debug)]
+pub struct ApiError {
+    pub status: StatusCode,
+    pub code: &'static str,
+    pub message: String,
+}
+
+impl ApiError {
+    pub fn new(status: StatusCode, code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            code,
+            message: message.into(),
+        }
+    }
+
+    /// 400 VALIDATION_ERROR
+    pub fn validation(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, "VALIDATION_ERROR", message)
+    }
+
+    /// 500 INTERNAL_ERROR（詳細はログにのみ出力する）
+    pub fn internal(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR, "INTERNAL_ERROR", message)
+    }
+}
+
+impl From<sea_orm::DbErr> for ApiError {
+    fn from(e: sea_orm::DbErr) -> Self {
+        tracing::error!("Database error: {}", e);
+        Self::internal("Internal server error")
+    }
+}
+
+impl From<ExportError> for ApiError {
+    fn from(e: ExportError) -> Self {
+        match e {
+            ExportError::Database(e) => e.into(),
+            ExportError::Busy => Self::new(
+                StatusCode::SERVICE_UNAVAILABLE,
+                "EXPORT_BUSY",
+                "Too many exports are in progress, please try again later",
+            ),
+            ExportError::InProgress(_) => {
+                Self::new(StatusCode::CONFLICT, "EXPORT_IN_PROGRESS", e.to_string())
+            }
+            ExportError::Storage(e) => {
+                tracing::error!("Export storage error: {}", e);
+                Self::internal("Internal server error")
+            }
+        }
+    }
+}
+
+impl From<AuthError> for ApiError {
+    fn from(e: AuthError) -> Self {
+        Self::new(e.status(), e.code(), e.message())
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        error_response(self.status, self.code, self.message)
+    }
+}
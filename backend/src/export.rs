@@ -0,0 +1,800 @@
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::fmt;
+use std::io::{Cursor, Write};
+use std::sync::{Arc, Mutex, PoisonError};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sea_orm::{
+    ColumnTrait, Condition, DatabaseConnection, DbErr, EntityTrait, PaginatorTrait, QueryFilter,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipWriter};
+
+use crate::entity::{accounts, sessions, users, verifications};
+use crate::middleware::AuthUser;
+use crate::storage::{BlobStore, BlobStoreError, PRIVATE_PREFIX};
+
+// ============================================================
+// 個人データのエクスポート（GDPR のアクセス権）
+// - ExportSource ごとに1つのテーブル（データの種類）を出力する
+//   業務テーブルを追加した場合は Exporter::register で登録する
+// - トークン・パスワードなどの秘密情報は "[REDACTED]" に置き換える
+// - 件数が多い場合はバックグラウンドで生成し、ジョブの状態を返す
+//   ジョブの状態と生成したファイルは BlobStore の PRIVATE_PREFIX 配下に保存するため、
+//   生成したインスタンス以外からも取得できる（EXPORT_JOB_TTL_SECONDS 後に破棄）
+// - ジョブはユーザーごとに1つ。生成中に再度リクエストされた場合は同じジョブを返す
+//   （生成中のジョブと形式が異なる場合は、完了するまで受け付けない）
+// ============================================================
+
+/// 秘密情報を置き換える値
+const REDACTED: &str = "[REDACTED]";
+
+const DEFAULT_SYNC_MAX_ROWS: u64 = 1000;
+const DEFAULT_JOB_TTL_SECONDS: i64 = 3600;
+const DEFAULT_MAX_RUNNING_JOBS: usize = 4;
+
+/// エクスポート対象のデータ
+#[async_trait]
+pub trait ExportSource: Send + Sync {
+    /// 出力のキー・ZIP 内のファイル名（"sessions" など）
+    fn name(&self) -> &'static str;
+    /// 件数（同期で返すかバックグラウンドで生成するかの判定に使う）
+    async fn count(&self, db: &DatabaseConnection, user: &AuthUser) -> Result<u64, DbErr>;
+    async fn export(&self, db: &DatabaseConnection, user: &AuthUser) -> Result<Value, DbErr>;
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Json,
+    Zip,
+}
+
+impl ExportFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Json => "json",
+            ExportFormat::Zip => "zip",
+        }
+    }
+
+    fn content_type(self) -> &'static str {
+        match self {
+            ExportFormat::Json => "application/json",
+            ExportFormat::Zip => "application/zip",
+        }
+    }
+}
+
+/// 生成したファイル
+pub struct ExportFile {
+    pub data: Vec<u8>,
+    pub content_type: &'static str,
+    pub filename: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportStatus {
+    Pending,
+    Ready,
+    Failed,
+}
+
+/// バックグラウンドのエクスポートジョブ（BlobStore に JSON で保存する）
+#[derive(Clone, Deserialize, Serialize)]
+pub struct ExportJob {
+    pub id: String,
+    pub user_id: String,
+    pub format: ExportFormat,
+    pub status: ExportStatus,
+    pub created_at: DateTime<Utc>,
+    /// ダウンロード時のファイル名（status が ready の場合のみ）
+    pub filename: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum ExportError {
+    Database(DbErr),
+    Storage(BlobStoreError),
+    /// このインスタンスで生成中のジョブが EXPORT_MAX_RUNNING_JOBS に達している
+    Busy,
+    /// 別の形式のジョブが生成中
+    InProgress(ExportFormat),
+}
+
+impl fmt::Display for ExportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExportError::Database(e) => write!(f, "{e}"),
+            ExportError::Storage(e) => write!(f, "{e}"),
+            ExportError::Busy => f.write_str("Too many export jobs are running"),
+            ExportError::InProgress(format) => {
+                write!(
+                    f,
+                    "An export job in {} format is running",
+                    format.extension()
+                )
+            }
+        }
+    }
+}
+
+impl From<DbErr> for ExportError {
+    fn from(e: DbErr) -> Self {
+        ExportError::Database(e)
+    }
+}
+
+impl From<BlobStoreError> for ExportError {
+    fn from(e: BlobStoreError) -> Self {
+        ExportError::Storage(e)
+    }
+}
+
+pub struct Exporter {
+    sources: Vec<Arc<dyn ExportSource>>,
+    /// ジョブの状態と生成したファイルの保存先
+    blob_store: Arc<dyn BlobStore>,
+    /// このインスタンスで生成中のジョブ（ユーザー ID → ジョブ）
+    running: Mutex<HashMap<String, ExportJob>>,
+    /// 同時に生成するジョブの上限（メモリ・DB 負荷の上限）
+    max_running_jobs: usize,
+    job_ttl: chrono::Duration,
+    /// これを超える件数はバックグラウンドで生成する
+    sync_max_rows: u64,
+}
+
+impl Exporter {
+    /// EXPORT_SYNC_MAX_ROWS / EXPORT_JOB_TTL_SECONDS / EXPORT_MAX_RUNNING_JOBS から生成し、
+    /// 認証テーブルを登録
+    pub fn from_env(blob_store: Arc<dyn BlobStore>) -> Self {
+        let sync_max_rows = env::var("EXPORT_SYNC_MAX_ROWS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_SYNC_MAX_ROWS);
+        let job_ttl = env::var("EXPORT_JOB_TTL_SECONDS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_JOB_TTL_SECONDS);
+        let max_running_jobs = env::var("EXPORT_MAX_RUNNING_JOBS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_MAX_RUNNING_JOBS);
+
+        Self {
+            sources: Vec::new(),
+            blob_store,
+            running: Mutex::new(HashMap::new()),
+            max_running_jobs,
+            job_ttl: chrono::Duration::seconds(job_ttl),
+            sync_max_rows,
+        }
+        .register(UsersSource)
+        .register(SessionsSource)
+        .register(AccountsSource)
+        .register(VerificationsSource)
+    }
+
+    /// エクスポート対象を追加
+    /// 使い方: Exporter::from_env(blob_store).register(ProjectsSource)
+    pub fn register(mut self, source: impl ExportSource + 'static) -> Self {
+        self.sources.push(Arc::new(source));
+        self
+    }
+
+    /// バックグラウンドで生成すべき件数か
+    pub async fn is_large(&self, db: &DatabaseConnection, user: &AuthUser) -> Result<bool, DbErr> {
+        let mut total = 0;
+        for source in &self.sources {
+            total += source.count(db, user).await?;
+        }
+        Ok(total > self.sync_max_rows)
+    }
+
+    /// すべてのエクスポート対象を集めてファイルを生成
+    pub async fn build(
+        &self,
+        db: &DatabaseConnection,
+        user: &AuthUser,
+        format: ExportFormat,
+    ) -> Result<ExportFile, DbErr> {
+        let mut data = BTreeMap::new();
+        for source in &self.sources {
+            data.insert(source.name(), source.export(db, user).await?);
+        }
+
+        let exported_at = Utc::now();
+        let filename = format!(
+            "export-{}-{}.{}",
+            user.id,
+            exported_at.format("%Y%m%d%H%M%S"),
+            format.extension()
+        );
+
+        let data = match format {
+            ExportFormat::Json => {
+                let document = json!({
+                    "exported_at": exported_at,
+                    "user_id": user.id,
+                    "data": data,
+                });
+                serde_json::to_vec_pretty(&document).map_err(json_error)?
+            }
+            ExportFormat::Zip => {
+                let manifest = json!({
+                    "exported_at": exported_at,
+                    "user_id": user.id,
+                    "files": data.keys().map(|name| format!("{name}.json")).collect::<Vec<_>>(),
+                });
+                let files = std::iter::once(("manifest", manifest)).chain(data);
+                zip_files(files).map_err(|e| DbErr::Custom(e.to_string()))?
+            }
+        };
+
+        Ok(ExportFile {
+            data,
+            content_type: format.content_type(),
+            filename,
+        })
+    }
+
+    /// バックグラウンドで生成を開始し、ジョブを返す
+    /// 同じユーザーのジョブが生成中の場合は、新しく開始せずにそのジョブを返す
+    /// （形式が異なる場合は ExportError::InProgress）
+    pub async fn start_job(
+        self: &Arc<Self>,
+        db: Arc<DatabaseConnection>,
+        user: AuthUser,
+        format: ExportFormat,
+    ) -> Result<ExportJob, ExportError> {
+        // 他のインスタンスで生成中のジョブ
+        if let Some(job) = self.stored_job(&user.id).await? {
+            if job.status == ExportStatus::Pending {
+                return running_job(job, format);
+            }
+        }
+
+        let job = {
+            let mut running = self.running.lock().unwrap_or_else(PoisonError::into_inner);
+            if let Some(job) = running.get(&user.id) {
+                return running_job(job.clone(), format);
+            }
+            if running.len() >= self.max_running_jobs {
+                return Err(ExportError::Busy);
+            }
+
+            let job = ExportJob {
+                id: uuid::Uuid::new_v4().to_string(),
+                user_id: user.id.clone(),
+                format,
+                status: ExportStatus::Pending,
+                created_at: Utc::now(),
+                filename: None,
+            };
+            running.insert(user.id.clone(), job.clone());
+            job
+        };
+
+        if let Err(e) = self.save_job(&job).await {
+            self.finish(&job.user_id);
+            return Err(e.into());
+        }
+
+        let exporter = Arc::clone(self);
+        let mut finished = job.clone();
+        tokio::spawn(async move {
+            match exporter.store_file(&db, &user, format).await {
+                Ok(filename) => {
+                    finished.status = ExportStatus::Ready;
+                    finished.filename = Some(filename);
+                }
+                Err(e) => {
+                    tracing::error!("Export job {} failed: {}", finished.id, e);
+                    finished.status = ExportStatus::Failed;
+                }
+            }
+            if let Err(e) = exporter.save_job(&finished).await {
+                tracing::error!("Failed to save export job {}: {}", finished.id, e);
+            }
+            exporter.finish(&finished.user_id);
+        });
+
+        Ok(job)
+    }
+
+    /// ユーザー自身のジョブを取得（他人のジョブ・破棄されたジョブは None）
+    pub async fn job(&self, id: &str, user_id: &str) -> Result<Option<ExportJob>, ExportError> {
+        Ok(self.stored_job(user_id).await?.filter(|job| job.id == id))
+    }
+
+    /// 生成済みのファイルを読み込む
+    pub async fn download(&self, job: &ExportJob) -> Result<Option<ExportFile>, ExportError> {
+        let (ExportStatus::Ready, Some(filename)) = (job.status, &job.filename) else {
+            return Ok(None);
+        };
+        let data = self
+            .blob_store
+            .get(&file_key(&job.user_id, job.format))
+            .await?;
+        Ok(data.map(|data| ExportFile {
+            data,
+            content_type: job.format.content_type(),
+            filename: filename.clone(),
+        }))
+    }
+
+    /// ユーザーのジョブとファイルを削除（退会ユーザーの完全削除時など）
+    pub async fn delete(&self, user_id: &str) -> Result<(), BlobStoreError> {
+        self.blob_store.delete(&job_key(user_id)).await?;
+        for format in [ExportFormat::Json, ExportFormat::Zip] {
+            self.blob_store.delete(&file_key(user_id, format)).await?;
+        }
+        Ok(())
+    }
+
+    async fn store_file(
+        &self,
+        db: &DatabaseConnection,
+        user: &AuthUser,
+        format: ExportFormat,
+    ) -> Result<String, ExportError> {
+        let file = self.build(db, user, format).await?;
+        self.blob_store
+            .put(&file_key(&user.id, format), file.data, file.content_type)
+            .await?;
+        Ok(file.filename)
+    }
+
+    /// 保存済みのジョブ（期限切れの場合は削除して None）
+    async fn stored_job(&self, user_id: &str) -> Result<Option<ExportJob>, ExportError> {
+        let Some(data) = self.blob_store.get(&job_key(user_id)).await? else {
+            return Ok(None);
+        };
+        let job = match serde_json::from_slice::<ExportJob>(&data) {
+            Ok(job) if job.created_at + self.job_ttl > Utc::now() => return Ok(Some(job)),
+            Ok(_) => None,
+            Err(e) => {
+                tracing::warn!("Discarding unreadable export job of {}: {}", user_id, e);
+                None
+            }
+        };
+        if let Err(e) = self.delete(user_id).await {
+            tracing::warn!("Failed to delete expired export of {}: {}", user_id, e);
+        }
+        Ok(job)
+    }
+
+    async fn save_job(&self, job: &ExportJob) -> Result<(), BlobStoreError> {
+        let data = serde_json::to_vec(job).map_err(|e| BlobStoreError::new(e.to_string()))?;
+        self.blob_store
+            .put(&job_key(&job.user_id), data, "application/json")
+            .await?;
+        Ok(())
+    }
+
+    fn finish(&self, user_id: &str) {
+        self.running
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(user_id);
+    }
+}
+
+/// 生成中のジョブを返す（要求された形式と異なる場合はエラー）
+fn running_job(job: ExportJob, format: ExportFormat) -> Result<ExportJob, ExportError> {
+    if job.format == format {
+        Ok(job)
+    } else {
+        Err(ExportError::InProgress(job.format))
+    }
+}
+
+/// ユーザーのジョブの保存先（ユーザーごとに1つ）
+fn job_key(user_id: &str) -> String {
+    format!("{PRIVATE_PREFIX}/exports/{user_id}/job.json")
+}
+
+/// 生成したファイルの保存先
+fn file_key(user_id: &str, format: ExportFormat) -> String {
+    format!(
+        "{PRIVATE_PREFIX}/exports/{user_id}/export.{}",
+        format.extension()
+    )
+}
+
+fn zip_files(
+    files: impl Iterator<Item = (&'static str, Value)>,
+) -> zip::result::ZipResult<Vec<u8>> {
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    for (name, value) in files {
+        zip.start_file(format!("{name}.json"), options)?;
+        let json = serde_json::to_vec_pretty(&value).map_err(std::io::Error::from)?;
+        zip.write_all(&json)?;
+    }
+    Ok(zip.finish()?.into_inner())
+}
+
+fn json_error(e: serde_json::Error) -> DbErr {
+    DbErr::Custom(format!("Failed to serialize export: {e}"))
+}
+
+/// 行を JSON に変換し、fields の値（null 以外）を REDACTED に置き換える
+fn redacted<T: Serialize>(rows: Vec<T>, fields: &[&str]) -> Result<Value, DbErr> {
+    let mut value = serde_json::to_value(rows).map_err(json_error)?;
+    for row in value.as_array_mut().into_iter().flatten() {
+        for field in fields {
+            if let Some(v) = row.get_mut(*field).filter(|v| !v.is_null()) {
+                *v = Value::from(REDACTED);
+            }
+        }
+    }
+    Ok(value)
+}
+
+// ============================================================
+// 認証テーブル（Better Auth）
+// ============================================================
+
+struct UsersSource;
+
+#[async_trait]
+impl ExportSource for UsersSource {
+    fn name(&self) -> &'static str {
+        "users"
+    }
+
+    async fn count(&self, _db: &DatabaseConnection, _user: &AuthUser) -> Result<u64, DbErr> {
+        Ok(1)
+    }
+
+    async fn export(&self, db: &DatabaseConnection, user: &AuthUser) -> Result<Value, DbErr> {
+        let rows = users::Entity::find_by_id(&user.id).all(db).await?;
+        redacted(rows, &[])
+    }
+}
+
+struct SessionsSource;
+
+#[async_trait]
+impl ExportSource for SessionsSource {
+    fn name(&self) -> &'static str {
+        "sessions"
+    }
+
+    async fn count(&self, db: &DatabaseConnection, user: &AuthUser) -> Result<u64, DbErr> {
+        sessions::Entity::find()
+            .filter(sessions::Column::UserId.eq(&user.id))
+            .count(db)
+            .await
+    }
+
+    async fn export(&self, db: &DatabaseConnection, user: &AuthUser) -> Result<Value, DbErr> {
+        let rows = sessions::Entity::find()
+            .filter(sessions::Column::UserId.eq(&user.id))
+            .all(db)
+            .await?;
+        redacted(rows, &["token"])
+    }
+}
+
+struct AccountsSource;
+
+#[async_trait]
+impl ExportSource for AccountsSource {
+    fn name(&self) -> &'static str {
+        "accounts"
+    }
+
+    async fn count(&self, db: &DatabaseConnection, user: &AuthUser) -> Result<u64, DbErr> {
+        accounts::Entity::find()
+            .filter(accounts::Column::UserId.eq(&user.id))
+            .count(db)
+            .await
+    }
+
+    async fn export(&self, db: &DatabaseConnection, user: &AuthUser) -> Result<Value, DbErr> {
+        let rows = accounts::Entity::find()
+            .filter(accounts::Column::UserId.eq(&user.id))
+            .all(db)
+            .await?;
+        redacted(
+            rows,
+            &["access_token", "refresh_token", "id_token", "password"],
+        )
+    }
+}
+
+/// verifications はユーザー ID を持たないため、identifier がメールアドレスそのもの
+/// （または Better Auth が付けるプレフィックス + メールアドレス）と完全に一致する行と、
+/// value がユーザー ID の行（パスワードリセット・アカウント削除・復元のトークン）を対象にする
+/// 他のユーザーの行を含めないよう、部分一致（LIKE）では探さない
+struct VerificationsSource;
+
+/// email-otp プラグインの identifier（"{type}-otp-{email}"）の type
+const OTP_TYPES: [&str; 3] = ["sign-in", "email-verification", "forget-password"];
+
+impl VerificationsSource {
+    /// このユーザーのメールアドレスを含む identifier
+    fn identifiers(email: &str) -> Vec<String> {
+        std::iter::once(email.to_string())
+            .chain(OTP_TYPES.iter().map(|kind| format!("{kind}-otp-{email}")))
+            .collect()
+    }
+
+    fn condition(user: &AuthUser) -> Condition {
+        Condition::any()
+            .add(verifications::Column::Identifier.is_in(Self::identifiers(&user.email)))
+            .add(verifications::Column::Value.eq(&user.id))
+    }
+}
+
+#[async_trait]
+impl ExportSource for VerificationsSource {
+    fn name(&self) -> &'static str {
+        "verifications"
+    }
+
+    async fn count(&self, db: &DatabaseConnection, user: &AuthUser) -> Result<u64, DbErr> {
+        verifications::Entity::find()
+            .filter(Self::condition(user))
+            .count(db)
+            .await
+    }
+
+    async fn export(&self, db: &DatabaseConnection, user: &AuthUser) -> Result<Value, DbErr> {
+        let identifiers = Self::identifiers(&user.email);
+        let rows: Vec<_> = verifications::Entity::find()
+            .filter(Self::condition(user))
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|mut row| {
+                // value は OTP などの秘密情報。メールアドレスの形式以外の identifier はトークンを含む
+                row.value = REDACTED.to_string();
+                if !identifiers.contains(&row.identifier) {
+                    row.identifier = REDACTED.to_string();
+                }
+                row
+            })
+            .collect();
+        redacted(rows, &[])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::FixedOffset;
+    use sea_orm::{DatabaseBackend, MockDatabase, Transaction};
+
+    use super::*;
+    use crate::device::DeviceInfo;
+    use crate::storage::MemoryBlobStore;
+
+    fn user() -> AuthUser {
+        AuthUser {
+            id: "user-1".into(),
+            name: "User 1".into(),
+            email: "a_b%@example.com".into(),
+            email_verified: true,
+            image: None,
+            roles: vec!["user".into()],
+            updated_at: Utc::now().fixed_offset(),
+            session_id: "session-1".into(),
+            device: DeviceInfo::parse(None),
+            impersonated_by: None,
+        }
+    }
+
+    fn now() -> DateTime<FixedOffset> {
+        Utc::now().fixed_offset()
+    }
+
+    /// 認証テーブルを登録しない Exporter（ジョブの保存・形式の確認用）
+    fn exporter(blob_store: Arc<MemoryBlobStore>) -> Arc<Exporter> {
+        Arc::new(Exporter {
+            sources: Vec::new(),
+            blob_store,
+            running: Mutex::new(HashMap::new()),
+            max_running_jobs: DEFAULT_MAX_RUNNING_JOBS,
+            job_ttl: chrono::Duration::seconds(DEFAULT_JOB_TTL_SECONDS),
+            sync_max_rows: DEFAULT_SYNC_MAX_ROWS,
+        })
+    }
+
+    fn verification(id: &str, identifier: &str, value: &str) -> verifications::Model {
+        verifications::Model {
+            id: id.into(),
+            identifier: identifier.into(),
+            value: value.into(),
+            expires_at: now(),
+            created_at: now(),
+            updated_at: now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn verifications_are_queried_by_exact_identifiers_and_redacted() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([[
+                verification("v1", "a_b%@example.com", "verify-token"),
+                verification("v2", "sign-in-otp-a_b%@example.com", "123456"),
+                verification("v3", "reset-password:secret-token", "user-1"),
+            ]])
+            .into_connection();
+
+        let value = VerificationsSource.export(&db, &user()).await.unwrap();
+
+        // 部分一致（LIKE）ではなく、identifier の完全一致と value = ユーザー ID で探す
+        assert_eq!(
+            db.into_transaction_log(),
+            [Transaction::from_sql_and_values(
+                DatabaseBackend::Postgres,
+                r#"SELECT "verifications"."id", "verifications"."identifier", "verifications"."value", "verifications"."expires_at", "verifications"."created_at", "verifications"."updated_at" FROM "verifications" WHERE "verifications"."identifier" IN ($1, $2, $3, $4) OR "verifications"."value" = $5"#,
+                [
+                    "a_b%@example.com".into(),
+                    "sign-in-otp-a_b%@example.com".into(),
+                    "email-verification-otp-a_b%@example.com".into(),
+                    "forget-password-otp-a_b%@example.com".into(),
+                    "user-1".into(),
+                ],
+            )]
+        );
+
+        let rows: Vec<_> = value
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|row| (row["identifier"].as_str(), row["value"].as_str()))
+            .collect();
+        assert_eq!(
+            rows,
+            [
+                (Some("a_b%@example.com"), Some(REDACTED)),
+                (Some("sign-in-otp-a_b%@example.com"), Some(REDACTED)),
+                // トークンを含む identifier も置き換える
+                (Some(REDACTED), Some(REDACTED)),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn session_and_account_secrets_are_redacted() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([[sessions::Model {
+                id: "session-1".into(),
+                user_id: "user-1".into(),
+                token: "session-token".into(),
+                expires_at: now(),
+                ip_address: Some("192.0.2.1".into()),
+                user_agent: None,
+                created_at: now(),
+                updated_at: now(),
+                impersonated_by: None,
+            }]])
+            .append_query_results([[accounts::Model {
+                id: "account-1".into(),
+                user_id: "user-1".into(),
+                account_id: "github-1".into(),
+                provider_id: "github".into(),
+                access_token: Some("access-token".into()),
+                refresh_token: None,
+                access_token_expires_at: None,
+                refresh_token_expires_at: None,
+                scope: Some("read:user".into()),
+                id_token: Some("id-token".into()),
+                password: Some("password-hash".into()),
+                created_at: now(),
+                updated_at: now(),
+            }]])
+            .into_connection();
+
+        let sessions = SessionsSource.export(&db, &user()).await.unwrap();
+        assert_eq!(sessions[0]["token"], REDACTED);
+        assert_eq!(sessions[0]["ip_address"], "192.0.2.1");
+
+        let accounts = AccountsSource.export(&db, &user()).await.unwrap();
+        for field in ["access_token", "id_token", "password"] {
+            assert_eq!(accounts[0][field], REDACTED, "{field}");
+        }
+        // 値がない場合は null のまま
+        assert_eq!(accounts[0]["refresh_token"], Value::Null);
+        assert_eq!(accounts[0]["scope"], "read:user");
+
+        let serialized = serde_json::to_string(&(sessions, accounts)).unwrap();
+        for secret in ["session-token", "access-token", "id-token", "password-hash"] {
+            assert!(!serialized.contains(secret), "{secret}");
+        }
+    }
+
+    #[tokio::test]
+    async fn jobs_and_files_are_stored_under_the_private_prefix() {
+        let store = Arc::new(MemoryBlobStore::default());
+        let exporter = exporter(store.clone());
+        let db = Arc::new(MockDatabase::new(DatabaseBackend::Postgres).into_connection());
+
+        let job = exporter
+            .start_job(db, user(), ExportFormat::Zip)
+            .await
+            .unwrap();
+
+        let job = loop {
+            let job = exporter.job(&job.id, "user-1").await.unwrap().unwrap();
+            if job.status != ExportStatus::Pending {
+                break job;
+            }
+            tokio::task::yield_now().await;
+        };
+        assert_eq!(job.status, ExportStatus::Ready);
+
+        let mut keys: Vec<_> = store.0.lock().unwrap().keys().cloned().collect();
+        keys.sort();
+        assert_eq!(
+            keys,
+            [
+                "private/exports/user-1/export.zip",
+                "private/exports/user-1/job.json",
+            ]
+        );
+        assert!(keys
+            .iter()
+            .all(|key| key.starts_with(&format!("{PRIVATE_PREFIX}/"))));
+
+        let file = exporter.download(&job).await.unwrap().unwrap();
+        assert_eq!(file.content_type, "application/zip");
+
+        // 他のユーザーからは見えない
+        assert!(exporter.job(&job.id, "user-2").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn pending_job_is_returned_only_for_the_same_format() {
+        let store = Arc::new(MemoryBlobStore::default());
+        let exporter = exporter(store);
+        let db = || Arc::new(MockDatabase::new(DatabaseBackend::Postgres).into_connection());
+
+        // 他のインスタンスで生成中のジョブ
+        let pending = ExportJob {
+            id: "job-1".into(),
+            user_id: "user-1".into(),
+            format: ExportFormat::Json,
+            status: ExportStatus::Pending,
+            created_at: Utc::now(),
+            filename: None,
+        };
+        exporter.save_job(&pending).await.unwrap();
+
+        let job = exporter
+            .start_job(db(), user(), ExportFormat::Json)
+            .await
+            .unwrap();
+        assert_eq!(job.id, "job-1");
+
+        let error = exporter
+            .start_job(db(), user(), ExportFormat::Zip)
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(error, ExportError::InProgress(ExportFormat::Json)));
+
+        // このインスタンスで生成中のジョブも同様
+        exporter.delete("user-1").await.unwrap();
+        exporter
+            .running
+            .lock()
+            .unwrap()
+            .insert("user-1".into(), pending);
+        let error = exporter
+            .start_job(db(), user(), ExportFormat::Zip)
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(error, ExportError::InProgress(ExportFormat::Json)));
+    }
+}
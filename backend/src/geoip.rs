@@ -0,0 +1,142 @@
+use std::collections::{BTreeMap, HashSet};
+use std::env;
+use std::net::IpAddr;
+
+use maxminddb::{geoip2, MaxMindDBError, Reader};
+use serde::Serialize;
+
+// ============================================================
+// GeoIP（IP アドレス → 国・都市）
+// - GEOIP_DATABASE_PATH の MaxMind 形式（.mmdb）ファイルをローカルで参照する
+//   （GeoLite2-City / GeoLite2-Country など。外部通信なし）
+// - ファイルが未設定・読み込めない場合は無効化し、位置情報は常に None
+// ============================================================
+
+/// 地名の表示言語（MaxMind の names のキー）
+const DEFAULT_LANGUAGE: &str = "en";
+
+/// IP アドレスから推定した位置情報
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct GeoLocation {
+    /// ISO 3166-1 alpha-2（例: "JP"）
+    pub country_code: Option<String>,
+    pub country: Option<String>,
+    /// Country データベースの場合は常に None
+    pub city: Option<String>,
+}
+
+pub struct GeoIp {
+    reader: Option<Reader<Vec<u8>>>,
+    language: String,
+}
+
+impl GeoIp {
+    /// GEOIP_DATABASE_PATH / GEOIP_LANGUAGE から生成
+    pub fn from_env() -> Self {
+        let language = env::var("GEOIP_LANGUAGE").unwrap_or_else(|_| DEFAULT_LANGUAGE.into());
+
+        let Ok(path) = env::var("GEOIP_DATABASE_PATH") else {
+            tracing::info!("GEOIP_DATABASE_PATH is not set, GeoIP lookup is disabled");
+            return Self {
+                reader: None,
+                language,
+            };
+        };
+
+        // 位置情報は補助的な情報のため、読み込みに失敗しても起動は継続する
+        let reader = match Reader::open_readfile(&path) {
+            Ok(reader) => {
+                tracing::info!("GeoIP database loaded: {}", path);
+                Some(reader)
+            }
+            Err(e) => {
+                tracing::warn!("Failed to load GeoIP database ({}): {}", path, e);
+                None
+            }
+        };
+
+        Self { reader, language }
+    }
+
+    /// sessions.ip_address の位置情報を取得
+    /// （無効時・プライベートアドレスなど見つからない場合は None）
+    pub fn lookup(&self, ip_address: Option<&str>) -> Option<GeoLocation> {
+        let reader = self.reader.as_ref()?;
+        let ip: IpAddr = ip_address?.trim().parse().ok()?;
+
+        let city: geoip2::City = match reader.lookup(ip) {
+            Ok(city) => city,
+            Err(MaxMindDBError::AddressNotFoundError(_)) => return None,
+            Err(e) => {
+                tracing::warn!("GeoIP lookup failed for {}: {}", ip, e);
+                return None;
+            }
+        };
+
+        let country = city.country.as_ref();
+        let location = GeoLocation {
+            country_code: country
+                .and_then(|c| c.iso_code)
+                .map(|code| code.to_string()),
+            country: country.and_then(|c| self.name(c.names.as_ref())),
+            city: city.city.as_ref().and_then(|c| self.name(c.names.as_ref())),
+        };
+
+        (location.country_code.is_some() || location.city.is_some()).then_some(location)
+    }
+
+    /// 設定した言語の地名（なければ英語）
+    fn name(&self, names: Option<&BTreeMap<&str, &str>>) -> Option<String> {
+        let names = names?;
+        names
+            .get(self.language.as_str())
+            .or_else(|| names.get(DEFAULT_LANGUAGE))
+            .map(|name| name.to_string())
+    }
+}
+
+/// ログイン（古い順）の国コードから、過去のログインにない国からのログインかを判定
+/// - 最初に国が判定できたログインは比較対象がないため false
+///   （それより前のログインが国を判定できない IP の場合も同じ）
+/// - 国が判定できないログインは false
+pub fn new_countries<'a>(countries: impl IntoIterator<Item = Option<&'a str>>) -> Vec<bool> {
+    let mut seen = HashSet::new();
+    countries
+        .into_iter()
+        .map(|country| match country {
+            Some(country) => {
+                let first = seen.is_empty();
+                seen.insert(country) && !first
+            }
+            None => false,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_country_is_not_new() {
+        assert_eq!(new_countries([]), Vec::<bool>::new());
+        assert_eq!(new_countries([Some("JP")]), [false]);
+    }
+
+    #[test]
+    fn only_countries_not_seen_before_are_new() {
+        assert_eq!(
+            new_countries([Some("JP"), Some("JP"), Some("US"), Some("JP"), Some("US")]),
+            [false, false, true, false, false]
+        );
+    }
+
+    #[test]
+    fn logins_without_country_are_skipped() {
+        // 国を判定できないログインの後でも、最初に判定できた国は比較対象がない
+        assert_eq!(
+            new_countries([None, None, Some("JP"), None, Some("US")]),
+            [false, false, false, false, true]
+        );
+    }
+}
@@ -0,0 +1,203 @@
+use axum::http::StatusCode;
+use axum_extra::extract::CookieJar;
+use chrono::{SubsecRound, Utc};
+use rand::{distributions::Alphanumeric, Rng};
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::Set, ColumnTrait, EntityTrait, QueryFilter, TransactionTrait,
+};
+use serde_json::json;
+
+use crate::audit::{self, AuditAction};
+use crate::entity::{sessions, users};
+use crate::error::ApiError;
+use crate::middleware::{
+    clear_session_cookies, parse_roles, removal_cookie, signed_cookie, verify_signed_value,
+    AuthError, AuthUser,
+};
+use crate::session_cache::SessionCache;
+use crate::AppState;
+
+// ============================================================
+// なりすまし（Better Auth の admin プラグインの impersonateUser / stopImpersonating 互換）
+// - 対象ユーザーのセッションを sessions.impersonated_by 付きで作成
+//   （有効期間は IMPERSONATION_SESSION_DURATION_SECONDS、延長しない）
+// - 管理者のセッショントークンは "{cookie_prefix}.admin_session" Cookie に退避し、終了時に戻す
+// - admin ロールのユーザーにはなりすませない
+// - 開始・終了は admin_audit_logs に記録する
+// ============================================================
+
+/// Better Auth と同じ長さのセッショントークン
+const TOKEN_LENGTH: usize = 32;
+
+/// なりすませないロール（Better Auth の adminRoles）
+const ADMIN_ROLE: &str = "admin";
+
+pub struct Impersonation {
+    pub session: sessions::Model,
+    /// 対象ユーザーのセッション Cookie と、管理者のセッションを退避する Cookie
+    pub cookies: CookieJar,
+}
+
+/// 対象ユーザーのなりすましセッションを作成
+pub async fn start(
+    state: &AppState,
+    admin: &AuthUser,
+    target_id: &str,
+) -> Result<Impersonation, ApiError> {
+    if target_id == admin.id {
+        return Err(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            "CANNOT_IMPERSONATE_YOURSELF",
+            "You cannot impersonate yourself",
+        ));
+    }
+
+    let config = &state.auth_config;
+    let txn = state.db.begin().await?;
+
+    let target = users::Entity::find_by_id(target_id)
+        .filter(users::Column::DeletedAt.is_null())
+        .one(&txn)
+        .await?
+        .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, "NOT_FOUND", "User not found"))?;
+    if parse_roles(target.role.as_deref())
+        .iter()
+        .any(|r| r == ADMIN_ROLE)
+    {
+        return Err(ApiError::new(
+            StatusCode::FORBIDDEN,
+            "CANNOT_IMPERSONATE_ADMIN",
+            "You cannot impersonate an admin",
+        ));
+    }
+
+    // 終了時に戻す管理者のセッション
+    let admin_session = sessions::Entity::find_by_id(&admin.session_id)
+        .one(&txn)
+        .await?
+        .ok_or(AuthError::SessionNotFound)?;
+
+    let now = Utc::now().trunc_subsecs(6);
+    let token: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(TOKEN_LENGTH)
+        .map(char::from)
+        .collect();
+
+    let session = sessions::ActiveModel {
+        id: Set(uuid::Uuid::new_v4().to_string()),
+        user_id: Set(target.id.clone()),
+        token: Set(token),
+        expires_at: Set((now + config.impersonation_session_duration).fixed_offset()),
+        // 操作しているのは管理者のため、管理者のセッションの端末情報を引き継ぐ
+        ip_address: Set(admin_session.ip_address.clone()),
+        user_agent: Set(admin_session.user_agent.clone()),
+        created_at: Set(now.fixed_offset()),
+        updated_at: Set(now.fixed_offset()),
+        impersonated_by: Set(Some(admin.id.clone())),
+    }
+    .insert(&txn)
+    .await?;
+
+    audit::record(
+        &txn,
+        &admin.id,
+        AuditAction::ImpersonationStart,
+        Some(&target.id),
+        json!({ "session_id": session.id, "expires_at": session.expires_at }),
+    )
+    .await?;
+
+    txn.commit().await?;
+
+    let cookies = CookieJar::new()
+        .add(signed_cookie(
+            config,
+            &config.session_cookie_names[0],
+            &session.token,
+            config.impersonation_session_duration,
+        ))
+        // Better Auth と同じ "{token}:{dontRememberMe}" 形式
+        .add(signed_cookie(
+            config,
+            &config.admin_session_cookie_name,
+            &format!("{}:", admin_session.token),
+            admin_session.expires_at.to_utc() - now,
+        ));
+
+    Ok(Impersonation { session, cookies })
+}
+
+/// なりすましを終了し、管理者のセッションに戻す
+///
+/// admin_session Cookie がない（Bearer 認証など）・管理者のセッションが期限切れの場合は
+/// なりすましセッションの削除のみ行い、セッション Cookie を削除する
+/// 戻り値の bool は管理者のセッションに戻したかどうか
+pub async fn stop(
+    state: &AppState,
+    user: &AuthUser,
+    cookies: &CookieJar,
+) -> Result<(CookieJar, bool), ApiError> {
+    let Some(admin_id) = &user.impersonated_by else {
+        return Err(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            "NOT_IMPERSONATING",
+            "You are not impersonating anyone",
+        ));
+    };
+
+    let config = &state.auth_config;
+    let txn = state.db.begin().await?;
+
+    let session = sessions::Entity::delete_many()
+        .filter(sessions::Column::Id.eq(&user.session_id))
+        .exec_with_returning(&txn)
+        .await?
+        .pop()
+        .ok_or(AuthError::SessionNotFound)?;
+
+    audit::record(
+        &txn,
+        admin_id,
+        AuditAction::ImpersonationStop,
+        Some(&user.id),
+        json!({ "session_id": session.id }),
+    )
+    .await?;
+
+    txn.commit().await?;
+
+    state
+        .session_cache
+        .invalidate_session(&SessionCache::key(&session.token));
+
+    let admin_token = cookies
+        .get(&config.admin_session_cookie_name)
+        .and_then(|cookie| verify_signed_value(cookie.value(), &config.secret))
+        .and_then(|value| value.split(':').next().map(str::to_string));
+    let admin_session = match admin_token {
+        Some(token) => {
+            sessions::Entity::find()
+                .filter(sessions::Column::Token.eq(token))
+                .filter(sessions::Column::UserId.eq(admin_id))
+                .filter(sessions::Column::ExpiresAt.gt(Utc::now()))
+                .one(state.db.as_ref())
+                .await?
+        }
+        None => None,
+    };
+
+    let restored = admin_session.is_some();
+    let jar = match admin_session {
+        Some(admin_session) => CookieJar::new().add(signed_cookie(
+            config,
+            &config.session_cookie_names[0],
+            &admin_session.token,
+            admin_session.expires_at.to_utc() - Utc::now(),
+        )),
+        None => clear_session_cookies(config),
+    }
+    .add(removal_cookie(&config.admin_session_cookie_name));
+
+    Ok((jar, restored))
+}
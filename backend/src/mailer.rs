@@ -0,0 +1,70 @@
+use std::env;
+
+use serde::Serialize;
+
+// ============================================================
+// メール送信
+// - フロントエンドの Better Auth と同じ Resend を使う（RESEND_API_KEY / EMAIL_FROM）
+// - RESEND_API_KEY が未設定の場合は送信せず、宛先と件名のみログに出力する（開発用）
+//   本文はトークン付きのリンクを含むため出力しない
+// ============================================================
+
+const RESEND_API_URL: &str = "https://api.resend.com/emails";
+
+pub struct Mailer {
+    client: reqwest::Client,
+    api_key: Option<String>,
+    from: String,
+}
+
+#[derive(Serialize)]
+struct ResendEmail<'a> {
+    from: &'a str,
+    to: [&'a str; 1],
+    subject: &'a str,
+    html: &'a str,
+}
+
+impl Mailer {
+    pub fn from_env() -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            api_key: env::var("RESEND_API_KEY")
+                .ok()
+                .filter(|key| !key.is_empty()),
+            from: env::var("EMAIL_FROM").unwrap_or_else(|_| "noreply@example.com".into()),
+        }
+    }
+
+    pub async fn send(&self, to: &str, subject: &str, html: &str) -> Result<(), String> {
+        let Some(api_key) = &self.api_key else {
+            tracing::warn!(
+                "RESEND_API_KEY is not set, email was not sent (to: {}, subject: {})",
+                to,
+                subject
+            );
+            return Ok(());
+        };
+
+        let response = self
+            .client
+            .post(RESEND_API_URL)
+            .bearer_auth(api_key)
+            .json(&ResendEmail {
+                from: &self.from,
+                to: [to],
+                subject,
+                html,
+            })
+            .send()
+            .await
+            .map_err(|e| format!("Failed to send email: {e}"))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(format!("Failed to send email: {status} {body}"));
+        }
+        Ok(())
+    }
+}
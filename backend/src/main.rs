@@ -1,5 +1,6 @@
 use std::env;
 use std::net::SocketAddr;
+use std::sync::Arc;
 
 use axum::Router;
 use sea_orm::Database;
@@ -7,13 +8,77 @@ use axum::http::{header, Method};
 use tower_http::cors::CorsLayer;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
+mod audit;
+mod authz;
+mod avatar;
+mod config;
+mod device;
 mod entity;
+mod error;
+mod export;
+mod geoip;
+mod impersonation;
+mod mailer;
 mod middleware;
+mod password;
+mod rate_limit;
 mod routes;
+mod session_cache;
+mod stats;
+mod storage;
+mod withdrawal;
+
+use crate::authz::AccessControl;
+use crate::config::{AuthConfig, ConfigError};
+use crate::export::Exporter;
+use crate::geoip::GeoIp;
+use crate::mailer::Mailer;
+use crate::password::PasswordHasher;
+use crate::session_cache::SessionCache;
+use crate::storage::BlobStore;
+use crate::withdrawal::RestoreRateLimit;
 
 #[derive(Clone)]
 pub struct AppState {
-    pub db: sea_orm::DatabaseConnection,
+    // Arc: テストで使う MockDatabase の接続は Clone できないため
+    pub db: Arc<sea_orm::DatabaseConnection>,
+    pub auth_config: Arc<AuthConfig>,
+    pub session_cache: SessionCache,
+    pub access_control: Arc<AccessControl>,
+    pub password_hasher: Arc<dyn PasswordHasher>,
+    pub geoip: Arc<GeoIp>,
+    pub blob_store: Arc<dyn BlobStore>,
+    pub exporter: Arc<Exporter>,
+    pub mailer: Arc<Mailer>,
+    pub restore_rate_limit: Arc<RestoreRateLimit>,
+}
+
+#[cfg(test)]
+impl AppState {
+    /// テスト用の状態（セッションキャッシュは無効）
+    pub(crate) fn for_tests(db: sea_orm::DatabaseConnection, auth_config: AuthConfig) -> Self {
+        let blob_store = storage::blob_store_from_env().unwrap();
+        Self {
+            db: Arc::new(db),
+            auth_config: Arc::new(auth_config),
+            session_cache: SessionCache::new(0, std::time::Duration::ZERO),
+            access_control: Arc::new(AccessControl::from_env().unwrap()),
+            password_hasher: password::hasher_from_env().unwrap(),
+            geoip: Arc::new(GeoIp::from_env()),
+            exporter: Arc::new(Exporter::from_env(blob_store.clone())),
+            blob_store,
+            mailer: Arc::new(Mailer::from_env()),
+            restore_rate_limit: Arc::new(RestoreRateLimit::new()),
+        }
+    }
+}
+
+/// 設定が不正な場合は、変数名とともにログに出して終了する
+fn config_or_exit<T>(result: Result<T, ConfigError>) -> T {
+    result.unwrap_or_else(|e| {
+        tracing::error!("Invalid configuration: {}", e);
+        std::process::exit(1);
+    })
 }
 
 #[tokio::main]
@@ -36,21 +101,72 @@ async fn main() {
         .await
         .expect("Failed to connect to database");
 
-    let state = AppState { db };
+    // 認証設定（Better Auth と共有するシークレットなど）
+    let auth_config = Arc::new(config_or_exit(AuthConfig::from_env()));
+
+    // セッションキャッシュ（他インスタンスの変更は LISTEN/NOTIFY で無効化）
+    let session_cache = SessionCache::new(
+        auth_config.session_cache_max_capacity,
+        auth_config.session_cache_ttl,
+    );
+    session_cache::spawn_invalidation_listener(&db, session_cache.clone());
+
+    // 権限定義（ロール → リソース/アクション）
+    let access_control = Arc::new(config_or_exit(AccessControl::from_env()));
+
+    // パスワードハッシュの実装（デフォルトは Better Auth 互換の scrypt）
+    let password_hasher = config_or_exit(password::hasher_from_env());
+
+    // GeoIP（データベースファイルがなければ無効）
+    let geoip = Arc::new(GeoIp::from_env());
+
+    // アップロードファイルの保存先（デフォルトはローカルディレクトリ）
+    let blob_store = config_or_exit(storage::blob_store_from_env());
+
+    // 個人データのエクスポート（業務テーブルは .register(...) で追加）
+    let exporter = Arc::new(Exporter::from_env(blob_store.clone()));
+
+    // メール送信（RESEND_API_KEY 未設定時はログ出力のみ）
+    let mailer = Arc::new(Mailer::from_env());
+
+    let state = AppState {
+        db: Arc::new(db),
+        auth_config,
+        session_cache,
+        access_control,
+        password_hasher,
+        geoip,
+        blob_store,
+        exporter,
+        mailer,
+        restore_rate_limit: Arc::new(RestoreRateLimit::new()),
+    };
+
+    // 猶予期間を過ぎた退会ユーザーの完全削除
+    withdrawal::spawn_purge_job(state.clone());
+
+    // 管理者向け統計のロールアップの更新（STATS_ROLLUP_REFRESH_SECONDS が 0 なら無効）
+    stats::spawn_rollup_job(state.clone());
 
     // CORS 設定
     let frontend_url = env::var("FRONTEND_URL").unwrap_or_else(|_| "http://localhost:3050".into());
     let cors = CorsLayer::new()
         .allow_origin(frontend_url.parse::<axum::http::HeaderValue>().unwrap())
-        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE, Method::OPTIONS])
-        .allow_headers([header::CONTENT_TYPE, header::AUTHORIZATION, header::COOKIE])
+        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::PATCH, Method::DELETE, Method::OPTIONS])
+        .allow_headers([header::CONTENT_TYPE, header::AUTHORIZATION, header::COOKIE, header::IF_MATCH])
+        .expose_headers([header::ETAG])
         .allow_credentials(true);
 
     // ルーター構築
-    let app = Router::new()
-        .nest("/api", routes::routes(state.clone()))
-        .layer(cors)
-        .with_state(state);
+    let mut app = Router::new().nest("/api", routes::routes(state.clone()));
+
+    // ローカル保存の場合、アバター画像を配信
+    // （エクスポートファイルなど PRIVATE_PREFIX 配下は配信しない）
+    if let Some(uploads) = storage::local_serve_router(&[avatar::KEY_PREFIX]) {
+        app = app.nest(storage::LOCAL_SERVE_PATH, uploads);
+    }
+
+    let app = app.layer(cors).with_state(state);
 
     // サーバー起動
     let port: u16 = env::var("SERVER_PORT")
@@ -62,5 +178,11 @@ async fn main() {
     tracing::info!("Server listening on {}", addr);
 
     let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+    // レート制限で接続元の IP を使うため ConnectInfo を付ける
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await
+    .unwrap();
 }
@@ -1,13 +1,24 @@
+use std::sync::{Arc, Mutex};
+
 use axum::{
     extract::{FromRequestParts, Request, State},
-    http::{request::Parts, StatusCode},
+    http::{header, request::Parts, HeaderMap, HeaderValue, StatusCode},
     middleware::Next,
-    response::Response,
+    response::{IntoResponse, Response},
 };
-use axum_extra::extract::CookieJar;
-use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
+use axum_extra::extract::cookie::{Cookie, CookieJar, SameSite};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use chrono::{DateTime, FixedOffset, Utc};
+use hmac::{Hmac, Mac};
+use percent_encoding::percent_decode_str;
+use sea_orm::{sea_query::Expr, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter};
+use sha2::Sha256;
 
+use crate::config::AuthConfig;
+use crate::device::DeviceInfo;
 use crate::entity::{sessions, users};
+use crate::error::error_response_with_details;
+use crate::session_cache::{CachedSession, SessionCache};
 use crate::AppState;
 
 /// 認証済みユーザー情報
@@ -18,90 +29,599 @@ pub struct AuthUser {
     pub email: String,
     pub email_verified: bool,
     pub image: Option<String>,
+    /// ロール（users.role をカンマ区切りで分割したもの）
+    pub roles: Vec<String>,
+    /// users.updated_at（プロフィールの ETag に使う）
+    pub updated_at: DateTime<FixedOffset>,
+    /// 現在のリクエストのセッション ID
+    pub session_id: String,
+    /// 現在のセッションの端末情報（sessions.user_agent を解析したもの）
+    pub device: DeviceInfo,
+    /// なりすましセッションの場合、開始した管理者のユーザー ID（sessions.impersonated_by）
+    pub impersonated_by: Option<String>,
+}
+
+impl AuthUser {
+    pub fn has_role(&self, role: &str) -> bool {
+        self.roles.iter().any(|r| r == role)
+    }
+
+    /// なりすまし中は実行させない操作（退会・連携解除など）のチェック
+    /// 使い方: user.ensure_not_impersonated()?;
+    pub fn ensure_not_impersonated(&self) -> Result<(), AuthError> {
+        if self.impersonated_by.is_some() {
+            let err = AuthError::ImpersonationNotAllowed;
+            err.log();
+            return Err(err);
+        }
+        Ok(())
+    }
+}
+
+/// ロールが未設定の場合のデフォルト（Better Auth の admin プラグインの defaultRole）
+const DEFAULT_ROLE: &str = "user";
+
+/// users.role（カンマ区切り）をロールの一覧に変換
+pub fn parse_roles(role: Option<&str>) -> Vec<String> {
+    let roles: Vec<String> = role
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|r| !r.is_empty())
+        .map(str::to_string)
+        .collect();
+
+    if roles.is_empty() {
+        vec![DEFAULT_ROLE.to_string()]
+    } else {
+        roles
+    }
 }
 
 /// リクエストから認証ユーザーを取得する拡張（middleware 用）
 #[derive(Clone)]
 pub struct AuthExtension(pub AuthUser);
 
+/// 認証失敗の理由
+#[derive(Debug)]
+pub enum AuthError {
+    /// Cookie / Bearer トークンが送られていない
+    MissingToken,
+    /// 署名が不正、またはトークンの形式が不正
+    InvalidToken,
+    /// トークンに対応するセッションが存在しない
+    SessionNotFound,
+    /// セッションの有効期限切れ
+    SessionExpired,
+    /// セッションのユーザーが存在しない、または退会済み
+    UserWithdrawn,
+    /// ユーザーが BAN されている（認証済みだがアクセス権がないため 403）
+    UserBanned {
+        reason: Option<String>,
+        /// None は無期限
+        expires_at: Option<DateTime<FixedOffset>>,
+    },
+    /// メールアドレスが未認証（認証済みだがアクセス権がないため 403）
+    EmailNotVerified,
+    /// 必要なロールを持っていない
+    InsufficientRole,
+    /// 必要な権限を持っていない
+    PermissionDenied,
+    /// なりすましセッションでは許可されていない操作
+    ImpersonationNotAllowed,
+    /// データベースエラー（認証失敗ではないため 503）
+    Database(DbErr),
+}
+
+impl AuthError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            AuthError::Database(_) => StatusCode::SERVICE_UNAVAILABLE,
+            AuthError::EmailNotVerified
+            | AuthError::UserBanned { .. }
+            | AuthError::InsufficientRole
+            | AuthError::PermissionDenied
+            | AuthError::ImpersonationNotAllowed => StatusCode::FORBIDDEN,
+            _ => StatusCode::UNAUTHORIZED,
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            AuthError::MissingToken => "UNAUTHORIZED",
+            AuthError::InvalidToken => "INVALID_TOKEN",
+            AuthError::SessionNotFound => "SESSION_NOT_FOUND",
+            AuthError::SessionExpired => "SESSION_EXPIRED",
+            AuthError::UserWithdrawn => "USER_WITHDRAWN",
+            AuthError::UserBanned { .. } => "USER_BANNED",
+            AuthError::EmailNotVerified => "EMAIL_NOT_VERIFIED",
+            AuthError::InsufficientRole | AuthError::PermissionDenied => "FORBIDDEN",
+            AuthError::ImpersonationNotAllowed => "IMPERSONATION_NOT_ALLOWED",
+            AuthError::Database(_) => "SERVICE_UNAVAILABLE",
+        }
+    }
+
+    pub fn message(&self) -> &'static str {
+        match self {
+            AuthError::MissingToken => "Unauthorized",
+            AuthError::InvalidToken => "Invalid session token",
+            AuthError::SessionNotFound => "Session not found",
+            AuthError::SessionExpired => "Session expired",
+            AuthError::UserWithdrawn => "User not found or withdrawn",
+            AuthError::UserBanned { .. } => "User is banned",
+            AuthError::EmailNotVerified => "Email not verified",
+            AuthError::InsufficientRole => "Insufficient role",
+            AuthError::PermissionDenied => "Permission denied",
+            AuthError::ImpersonationNotAllowed => "Not allowed while impersonating",
+            // DB エラーの詳細はクライアントに返さない
+            AuthError::Database(_) => "Authentication service unavailable",
+        }
+    }
+
+    /// レスポンスの details（BAN の理由・期限）
+    pub fn details(&self) -> Option<serde_json::Value> {
+        match self {
+            AuthError::UserBanned { reason, expires_at } => Some(serde_json::json!({
+                "reason": reason,
+                "expires_at": expires_at,
+            })),
+            _ => None,
+        }
+    }
+
+    /// 失敗理由をログに出力
+    pub(crate) fn log(&self) {
+        match self {
+            AuthError::Database(e) => {
+                tracing::error!("Authentication failed: database error: {}", e)
+            }
+            AuthError::MissingToken => tracing::trace!("Authentication failed: no session token"),
+            _ => tracing::debug!("Authentication failed: {}", self.code()),
+        }
+    }
+}
+
+impl From<DbErr> for AuthError {
+    fn from(e: DbErr) -> Self {
+        AuthError::Database(e)
+    }
+}
+
+impl IntoResponse for AuthError {
+    fn into_response(self) -> Response {
+        error_response_with_details(self.status(), self.code(), self.message(), self.details())
+    }
+}
+
+/// Better Auth の署名（HMAC-SHA256 を Base64 エンコードしたもの）の長さ
+const SIGNATURE_LENGTH: usize = 44;
+
+/// 署名付きの値 `{value}.{signature}` を検証し、署名が正しければ value 部分を返す
+///
+/// Better Auth（better-call の signCookieValue）と同じく、
+/// 最後の '.' で分割し、value を BETTER_AUTH_SECRET で HMAC-SHA256 した結果と照合する
+pub(crate) fn verify_signed_value(signed: &str, secret: &str) -> Option<String> {
+    let (value, signature) = signed.rsplit_once('.')?;
+    if value.is_empty() || signature.len() != SIGNATURE_LENGTH || !signature.ends_with('=') {
+        return None;
+    }
+
+    let signature = STANDARD.decode(signature).ok()?;
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).ok()?;
+    mac.update(value.as_bytes());
+    // verify_slice は定数時間で比較する
+    mac.verify_slice(&signature).ok()?;
+
+    Some(value.to_string())
+}
+
+/// value に署名し `{value}.{signature}` を返す（verify_signed_value の逆）
+pub(crate) fn sign_value(value: &str, secret: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC can take key of any size");
+    mac.update(value.as_bytes());
+    format!("{value}.{}", STANDARD.encode(mac.finalize().into_bytes()))
+}
+
+/// リクエストから取り出したセッショントークン
+struct SessionToken {
+    token: String,
+    /// Cookie から取得した場合はその Cookie 名（Bearer トークンの場合は None）
+    cookie_name: Option<String>,
+}
+
 /// Better Auth の Cookie からセッショントークンを取得
-fn extract_session_token(cookies: &CookieJar) -> Option<String> {
-    // Better Auth は "better-auth.session_token" という名前で Cookie を設定
-    // フォーマット: {token}.{signature}
-    cookies
-        .get("better-auth.session_token")
-        .map(|c| c.value().to_string())
-        .and_then(|value| {
-            // トークン部分のみを抽出（署名部分は除外）
-            value.split('.').next().map(|s| s.to_string())
+fn extract_cookie_token(
+    cookies: &CookieJar,
+    config: &AuthConfig,
+) -> Result<SessionToken, AuthError> {
+    // Better Auth は "{cookiePrefix}.session_token"（HTTPS では "__Secure-" 付き）で Cookie を設定
+    // フォーマット: {token}.{signature}（URL エンコード済み）
+    // CookieJar は Cookie::parse_encoded で読み込むため、value() は URL デコード済み
+    // 候補の Cookie 名を設定の順に探し、最初に見つかったものを使う
+    let cookie = config
+        .session_cookie_names
+        .iter()
+        .find_map(|name| cookies.get(name))
+        .ok_or(AuthError::MissingToken)?;
+
+    // 署名が一致しない Cookie は DB を引く前に拒否
+    let token =
+        verify_signed_value(cookie.value(), &config.secret).ok_or(AuthError::InvalidToken)?;
+    Ok(SessionToken {
+        token,
+        cookie_name: Some(cookie.name().to_string()),
+    })
+}
+
+/// Better Auth の dont_remember Cookie（署名付きの "true"）があるか
+///
+/// 「ログイン状態を保持しない」でログインしたセッションは、Better Auth と同じく有効期限を延長しない
+fn has_dont_remember_cookie(cookies: &CookieJar, config: &AuthConfig) -> bool {
+    config
+        .dont_remember_cookie_names
+        .iter()
+        .filter_map(|name| cookies.get(name))
+        .any(|cookie| {
+            verify_signed_value(cookie.value(), &config.secret).as_deref() == Some("true")
         })
 }
 
-/// Cookie とセッションからユーザーを取得する共通関数
-async fn get_user_from_session(
-    db: &DatabaseConnection,
+/// Better Auth と同じ属性の Cookie を生成
+fn auth_cookie(name: &str, value: String) -> Cookie<'static> {
+    Cookie::build((name.to_string(), value))
+        .path("/")
+        .http_only(true)
+        .same_site(SameSite::Lax)
+        // "__Secure-" 付きの Cookie は Secure 属性が必須
+        .secure(name.starts_with("__Secure-"))
+        .build()
+}
+
+/// 署名付きの Cookie を設定する Set-Cookie を生成
+/// name はセッション Cookie の候補の先頭（Better Auth が設定するもの）
+pub fn signed_cookie(
+    config: &AuthConfig,
+    name: &str,
+    value: &str,
+    max_age: chrono::Duration,
+) -> Cookie<'static> {
+    let mut cookie = auth_cookie(name, sign_value(value, &config.secret));
+    cookie.set_max_age(time::Duration::seconds(max_age.num_seconds().max(0)));
+    cookie
+}
+
+/// Cookie を削除する Set-Cookie を生成
+pub fn removal_cookie(name: &str) -> Cookie<'static> {
+    let mut cookie = auth_cookie(name, String::new());
+    cookie.make_removal();
+    cookie
+}
+
+/// セッション Cookie を削除する Set-Cookie を生成
+pub fn clear_session_cookies(config: &AuthConfig) -> CookieJar {
+    config
+        .session_cookie_names
+        .iter()
+        .fold(CookieJar::new(), |jar, name| jar.add(removal_cookie(name)))
+}
+
+/// Authorization ヘッダーから Bearer トークンの値を取り出す
+/// Bearer 以外のスキーム（Basic など）は None
+fn bearer_value(headers: &HeaderMap) -> Option<&str> {
+    let value = headers.get(header::AUTHORIZATION)?.to_str().ok()?;
+    let (scheme, credentials) = value.split_once(' ')?;
+    scheme
+        .eq_ignore_ascii_case("bearer")
+        .then(|| credentials.trim())
+}
+
+/// Bearer トークンからセッショントークンを取得
+///
+/// Better Auth の bearer プラグインと同じく、以下の2形式を受け付ける
+/// - 署名付き: `{token}.{signature}`（URL エンコードされていてもよい）
+/// - 署名なし: `{token}`（BEARER_ALLOW_UNSIGNED_TOKENS=true の場合のみ。安全ではない）
+fn extract_bearer_token(credentials: &str, config: &AuthConfig) -> Result<String, AuthError> {
+    let decoded = percent_decode_str(credentials)
+        .decode_utf8()
+        .map_err(|_| AuthError::InvalidToken)?;
+
+    if decoded.contains('.') {
+        verify_signed_value(&decoded, &config.secret).ok_or(AuthError::InvalidToken)
+    } else if config.bearer_allow_unsigned && !decoded.is_empty() {
+        Ok(decoded.into_owned())
+    } else {
+        Err(AuthError::InvalidToken)
+    }
+}
+
+/// リクエストヘッダーからセッショントークンを取得
+///
+/// 優先順位: `Authorization: Bearer` > Cookie
+/// Bearer トークンが送られている場合はそれだけで判定し、
+/// 不正でも Cookie にはフォールバックしない（Better Auth の bearer プラグインと同じ挙動）
+fn extract_session_token(
+    headers: &HeaderMap,
     cookies: &CookieJar,
-) -> Option<AuthUser> {
-    // Cookie からトークンを取得
-    let token = extract_session_token(cookies)?;
+    config: &AuthConfig,
+) -> Result<SessionToken, AuthError> {
+    if let Some(credentials) = bearer_value(headers) {
+        return extract_bearer_token(credentials, config).map(|token| SessionToken {
+            token,
+            cookie_name: None,
+        });
+    }
+
+    extract_cookie_token(cookies, config)
+}
+
+/// リクエスト（Bearer トークン / Cookie）とセッションからユーザーを取得する共通関数
+/// 失敗した場合は理由をログに出力する
+async fn get_user_from_session(state: &AppState, parts: &Parts) -> Result<AuthUser, AuthError> {
+    let result = load_auth_user(state, parts).await;
+    if let Err(e) = &result {
+        e.log();
+    }
+    result
+}
+
+async fn load_auth_user(state: &AppState, parts: &Parts) -> Result<AuthUser, AuthError> {
+    let db = state.db.as_ref();
+    let config = &state.auth_config;
+
+    // Bearer トークンまたは Cookie からトークンを取得
+    let cookies = CookieJar::from_headers(&parts.headers);
+    let token = extract_session_token(&parts.headers, &cookies, config)?;
+    let dont_remember = has_dont_remember_cookie(&cookies, config);
+
+    // キャッシュにあれば DB を参照しない
+    // （更新が必要なセッションは DB 側の処理に回す）
+    let cache_key = SessionCache::key(&token.token);
+    if let Some(cached) = state.session_cache.get(&cache_key) {
+        if cached.user.impersonated_by.is_some()
+            || dont_remember
+            || !should_refresh_session(cached.expires_at, config)
+        {
+            return Ok(cached.user);
+        }
+    }
 
     // セッションをデータベースから検索
     let session = sessions::Entity::find()
-        .filter(sessions::Column::Token.eq(&token))
+        .filter(sessions::Column::Token.eq(&token.token))
         .one(db)
-        .await
-        .ok()??;
+        .await?
+        .ok_or(AuthError::SessionNotFound)?;
 
     // セッションの有効期限をチェック
-    if session.expires_at < chrono::Utc::now() {
-        return None;
+    if session.expires_at < Utc::now() {
+        return Err(AuthError::SessionExpired);
     }
 
     // ユーザー情報を取得
     let user = users::Entity::find_by_id(&session.user_id)
         .one(db)
-        .await
-        .ok()??;
+        .await?
+        .ok_or(AuthError::UserWithdrawn)?;
 
-    // 退会済みユーザーは None
+    // 退会済みユーザーはエラー
     if user.deleted_at.is_some() {
-        return None;
+        return Err(AuthError::UserWithdrawn);
+    }
+
+    // BAN 中のユーザーはエラー（期限を過ぎた BAN は解除して続行）
+    if user.banned == Some(true) {
+        match user.ban_expires {
+            Some(expires_at) if expires_at <= Utc::now() => lift_expired_ban(db, &user).await,
+            _ => {
+                return Err(AuthError::UserBanned {
+                    reason: user.ban_reason,
+                    expires_at: user.ban_expires,
+                })
+            }
+        }
+    }
+
+    // スライディング有効期限: updateAge を過ぎていれば期限を延長
+    // なりすましセッション（短時間で終わらせる）と dont_remember のセッションは延長しない
+    let mut expires_at: DateTime<Utc> = session.expires_at.into();
+    if session.impersonated_by.is_none()
+        && !dont_remember
+        && should_refresh_session(expires_at, config)
+    {
+        if let Some(renewed) = refresh_session(db, &session, config).await {
+            expires_at = renewed;
+
+            // Cookie の Max-Age も延長する（Bearer トークンはクライアントが管理するため不要）
+            if let (Some(name), Some(refreshed)) = (
+                &token.cookie_name,
+                parts.extensions.get::<RefreshedSessionCookie>(),
+            ) {
+                refreshed.set(signed_cookie(
+                    config,
+                    name,
+                    &token.token,
+                    config.session_expires_in,
+                ));
+            }
+        }
     }
 
-    Some(AuthUser {
+    let auth_user = AuthUser {
         id: user.id,
         name: user.name,
         email: user.email,
         email_verified: user.email_verified,
         image: user.image,
-    })
+        roles: parse_roles(user.role.as_deref()),
+        updated_at: user.updated_at,
+        session_id: session.id.clone(),
+        device: DeviceInfo::parse(session.user_agent.as_deref()),
+        impersonated_by: session.impersonated_by.clone(),
+    };
+
+    state.session_cache.insert(
+        cache_key,
+        CachedSession {
+            user: auth_user.clone(),
+            expires_at,
+        },
+    );
+
+    Ok(auth_user)
+}
+
+/// 期限を過ぎた BAN を解除（Better Auth の admin プラグインと同じく、次のログイン時に解除する）
+///
+/// ban_expires が読み込み時の値のままの場合のみ更新するため、
+/// 同時に再 BAN された場合はそちらを優先する
+async fn lift_expired_ban(db: &DatabaseConnection, user: &users::Model) {
+    let result = users::Entity::update_many()
+        .col_expr(users::Column::Banned, Expr::value(false))
+        .col_expr(
+            users::Column::BanReason,
+            Expr::value(Option::<String>::None),
+        )
+        .col_expr(
+            users::Column::BanExpires,
+            Expr::value(Option::<DateTime<Utc>>::None),
+        )
+        .filter(users::Column::Id.eq(&user.id))
+        .filter(users::Column::BanExpires.eq(user.ban_expires))
+        .exec(db)
+        .await;
+
+    match result {
+        Ok(_) => tracing::info!("Expired ban lifted: {}", user.id),
+        // 解除に失敗しても、期限を過ぎているため認証は継続する
+        Err(e) => tracing::warn!("Failed to lift expired ban of {}: {}", user.id, e),
+    }
+}
+
+/// セッションの有効期限を延長すべきか（Better Auth の updateAge と同じ判定）
+///
+/// 最後に期限を設定した時刻（expires_at - expiresIn）から updateAge 以上経過していれば延長する
+fn should_refresh_session(expires_at: DateTime<Utc>, config: &AuthConfig) -> bool {
+    if config.session_disable_refresh {
+        return false;
+    }
+    expires_at - config.session_expires_in + config.session_update_age <= Utc::now()
+}
+
+/// セッションの expires_at / updated_at を更新し、新しい有効期限を返す
+///
+/// expires_at が読み込み時の値のままの場合のみ更新するため、
+/// 同時リクエストや他インスタンスと競合しても1ウィンドウにつき書き込みは1回
+async fn refresh_session(
+    db: &DatabaseConnection,
+    session: &sessions::Model,
+    config: &AuthConfig,
+) -> Option<DateTime<Utc>> {
+    let now = Utc::now();
+    let expires_at = now + config.session_expires_in;
+
+    let result = sessions::Entity::update_many()
+        .col_expr(sessions::Column::ExpiresAt, Expr::value(expires_at))
+        .col_expr(sessions::Column::UpdatedAt, Expr::value(now))
+        .filter(sessions::Column::Id.eq(&session.id))
+        .filter(sessions::Column::ExpiresAt.eq(session.expires_at))
+        .exec(db)
+        .await;
+
+    match result {
+        Ok(res) if res.rows_affected > 0 => Some(expires_at),
+        // 他のリクエストが先に延長済み
+        Ok(_) => None,
+        Err(e) => {
+            // 延長に失敗しても認証自体は継続する
+            tracing::warn!("Failed to refresh session {}: {}", session.id, e);
+            None
+        }
+    }
+}
+
+/// 必須認証の共通処理（middleware と RequireAuth で共有）
+///
+/// 既に middleware で認証済みの場合はその結果を再利用し、DB を二重に引かない
+pub(crate) async fn authenticate(parts: &Parts, state: &AppState) -> Result<AuthUser, AuthError> {
+    if let Some(AuthExtension(user)) = parts.extensions.get::<AuthExtension>() {
+        return Ok(user.clone());
+    }
+    get_user_from_session(state, parts).await
+}
+
+// ============================================================
+// セッション Cookie の再発行
+// - スライディング有効期限で延長したセッションの Cookie を Max-Age を延ばして再発行する
+// - extractor はレスポンスを変更できないため、session_cookie_middleware がリクエストごとの
+//   RefreshedSessionCookie を extensions に入れ、認証処理が設定した Cookie をレスポンスに追加する
+// ============================================================
+
+/// 再発行するセッション Cookie（リクエストごとに共有）
+#[derive(Clone, Default)]
+pub struct RefreshedSessionCookie(Arc<Mutex<Option<Cookie<'static>>>>);
+
+impl RefreshedSessionCookie {
+    fn set(&self, cookie: Cookie<'static>) {
+        if let Ok(mut slot) = self.0.lock() {
+            *slot = Some(cookie);
+        }
+    }
+
+    fn take(&self) -> Option<Cookie<'static>> {
+        self.0.lock().ok().and_then(|mut slot| slot.take())
+    }
+}
+
+/// 延長したセッションの Cookie をレスポンスに追加するミドルウェア（/api 全体に適用）
+pub async fn session_cookie_middleware(mut request: Request, next: Next) -> Response {
+    let refreshed = RefreshedSessionCookie::default();
+    request.extensions_mut().insert(refreshed.clone());
+
+    let mut response = next.run(request).await;
+
+    if let Some(cookie) = refreshed.take() {
+        // ハンドラが同じ Cookie を設定・削除している場合（退会・なりすましの終了など）はそちらを優先
+        let prefix = format!("{}=", cookie.name());
+        let overridden = response
+            .headers()
+            .get_all(header::SET_COOKIE)
+            .iter()
+            .any(|value| value.as_bytes().starts_with(prefix.as_bytes()));
+        if !overridden {
+            if let Ok(value) = HeaderValue::from_str(&cookie.encoded().to_string()) {
+                response.headers_mut().append(header::SET_COOKIE, value);
+            }
+        }
+    }
+
+    response
 }
 
 // ============================================================
 // アプローチ1: middleware（必須認証ルート用）
 // - Router 全体にレイヤーとして適用
-// - 認証失敗で即 401
+// - 認証失敗で即 401（DB エラーは 503）
 // ============================================================
 
 /// 認証ミドルウェア（必須認証用）
 pub async fn auth_middleware(
     State(state): State<AppState>,
-    cookies: CookieJar,
-    mut request: Request,
+    request: Request,
     next: Next,
-) -> Result<Response, StatusCode> {
-    let auth_user = get_user_from_session(&state.db, &cookies)
-        .await
-        .ok_or(StatusCode::UNAUTHORIZED)?;
+) -> Result<Response, AuthError> {
+    let (mut parts, body) = request.into_parts();
+    let auth_user = authenticate(&parts, &state).await?;
 
-    request.extensions_mut().insert(AuthExtension(auth_user));
-    Ok(next.run(request).await)
+    parts.extensions.insert(AuthExtension(auth_user));
+    Ok(next.run(Request::from_parts(parts, body)).await)
 }
 
 // ============================================================
 // アプローチ2: FromRequestParts（任意認証用）
 // - ハンドラ引数で OptionalAuthUser として受け取る
 // - 認証失敗でも処理継続（user が None になるだけ）
+// - DB エラーのみ 503 で失敗（ゲスト扱いにはしない）
 // ============================================================
 
 /// 任意認証用のラッパー型
@@ -109,17 +629,541 @@ pub async fn auth_middleware(
 pub struct OptionalAuthUser(pub Option<AuthUser>);
 
 impl FromRequestParts<AppState> for OptionalAuthUser {
-    type Rejection = std::convert::Infallible; // 絶対に失敗しない
+    type Rejection = AuthError;
 
     async fn from_request_parts(
         parts: &mut Parts,
         state: &AppState,
     ) -> Result<Self, Self::Rejection> {
-        // CookieJar を手動で取得
-        let cookies = CookieJar::from_headers(&parts.headers);
-
         // 認証に失敗しても None を返すだけ（エラーにならない）
-        let user = get_user_from_session(&state.db, &cookies).await;
-        Ok(OptionalAuthUser(user))
+        match get_user_from_session(state, parts).await {
+            Ok(user) => Ok(OptionalAuthUser(Some(user))),
+            Err(e @ AuthError::Database(_)) => Err(e),
+            Err(_) => Ok(OptionalAuthUser(None)),
+        }
+    }
+}
+
+// ============================================================
+// アプローチ3: FromRequestParts（必須認証用）
+// - ハンドラ引数で RequireAuth として受け取る
+// - middleware を使わずにハンドラ単位で認証必須にできる
+//   （公開 API と同じ Router に混在させられる）
+// - 認証失敗時の挙動は middleware と同じ（401 / DB エラーは 503）
+// ============================================================
+
+/// 必須認証用のラッパー型
+/// 使い方: async fn handler(RequireAuth(user): RequireAuth) -> ...
+pub struct RequireAuth(pub AuthUser);
+
+impl FromRequestParts<AppState> for RequireAuth {
+    type Rejection = AuthError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        authenticate(parts, state).await.map(RequireAuth)
+    }
+}
+
+// ============================================================
+// メール認証済みユーザーの強制
+// - ルートグループ単位: verified_email_middleware（routes::routes で指定）
+// - ハンドラ単位: VerifiedUser extractor
+// - 未ログインは 401、メール未認証は 403（EMAIL_NOT_VERIFIED）
+// ============================================================
+
+/// メール認証済みでなければ EmailNotVerified
+fn ensure_email_verified(user: AuthUser) -> Result<AuthUser, AuthError> {
+    if !user.email_verified {
+        let err = AuthError::EmailNotVerified;
+        err.log();
+        return Err(err);
+    }
+    Ok(user)
+}
+
+/// 認証ミドルウェア（メール認証済み必須）
+pub async fn verified_email_middleware(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Result<Response, AuthError> {
+    let (mut parts, body) = request.into_parts();
+    let auth_user = ensure_email_verified(authenticate(&parts, &state).await?)?;
+
+    parts.extensions.insert(AuthExtension(auth_user));
+    Ok(next.run(Request::from_parts(parts, body)).await)
+}
+
+/// メール認証済みユーザー用のラッパー型
+/// 使い方: async fn handler(VerifiedUser(user): VerifiedUser) -> ...
+pub struct VerifiedUser(pub AuthUser);
+
+impl FromRequestParts<AppState> for VerifiedUser {
+    type Rejection = AuthError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let user = authenticate(parts, state).await?;
+        ensure_email_verified(user).map(VerifiedUser)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{body::Body, extract::Extension, routing::get, Json, Router};
+    use sea_orm::{DatabaseBackend, MockDatabase, MockExecResult};
+    use tower::ServiceExt;
+
+    use super::*;
+
+    // Better Auth（better-call の signCookieValue）が Set-Cookie に書き込む値と同じ方法で生成したフィクスチャ
+    // value + "." + base64(HMAC-SHA256(secret, value)) を encodeURIComponent したもの
+    const SECRET: &str = "your-secret-key-at-least-32-characters-long";
+    const OTHER_SECRET: &str = "another-secret-key-at-least-32-characters";
+    const TOKEN: &str = "Zr3AAbSX0cK7UQyPz1wLe9T2nH6mJq4V";
+    const SIGNED: &str =
+        "Zr3AAbSX0cK7UQyPz1wLe9T2nH6mJq4V.QuIPiiLvHk4hLL+NfZB+NzTrEtxNidFucLmjtm6AaYg=";
+    const COOKIE_VALUE: &str =
+        "Zr3AAbSX0cK7UQyPz1wLe9T2nH6mJq4V.QuIPiiLvHk4hLL%2BNfZB%2BNzTrEtxNidFucLmjtm6AaYg%3D";
+    const SIGNED_WITH_OTHER_SECRET: &str =
+        "Zr3AAbSX0cK7UQyPz1wLe9T2nH6mJq4V.2n/x6arjGvzur2gHHrRDXrO+dsWv9/c/DpLfWmpYcR0=";
+
+    fn cookie_headers(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::COOKIE,
+            format!("better-auth.session_token={value}")
+                .parse()
+                .unwrap(),
+        );
+        headers
+    }
+
+    #[test]
+    fn verifies_better_auth_signed_value() {
+        assert_eq!(verify_signed_value(SIGNED, SECRET).as_deref(), Some(TOKEN));
+        assert_eq!(
+            verify_signed_value(
+                "h8Kq2LmN5pR7sT9vW1xY3zA6bC4dE0fG.hKnQunnfeeOnDJZZ3++s4/Z1+FeeumXqSXvxB2AKK4E=",
+                SECRET
+            )
+            .as_deref(),
+            Some("h8Kq2LmN5pR7sT9vW1xY3zA6bC4dE0fG")
+        );
+    }
+
+    #[test]
+    fn sign_value_matches_better_auth() {
+        assert_eq!(sign_value(TOKEN, SECRET), SIGNED);
+        assert_eq!(sign_value(TOKEN, OTHER_SECRET), SIGNED_WITH_OTHER_SECRET);
+    }
+
+    #[test]
+    fn rejects_tampered_signature() {
+        let tampered = SIGNED.replacen("QuIP", "QuIQ", 1);
+        assert_eq!(verify_signed_value(&tampered, SECRET), None);
+    }
+
+    #[test]
+    fn rejects_tampered_value() {
+        let tampered = SIGNED.replacen("Zr3A", "Zr3B", 1);
+        assert_eq!(verify_signed_value(&tampered, SECRET), None);
+    }
+
+    #[test]
+    fn rejects_wrong_secret() {
+        assert_eq!(verify_signed_value(SIGNED, OTHER_SECRET), None);
+        assert_eq!(verify_signed_value(SIGNED_WITH_OTHER_SECRET, SECRET), None);
+    }
+
+    #[test]
+    fn rejects_unsigned_raw_token() {
+        assert_eq!(verify_signed_value(TOKEN, SECRET), None);
+        assert_eq!(verify_signed_value(&format!("{TOKEN}."), SECRET), None);
+
+        let config = AuthConfig::for_tests(SECRET);
+        let cookies = CookieJar::from_headers(&cookie_headers(TOKEN));
+        assert!(matches!(
+            extract_cookie_token(&cookies, &config).map(|t| t.token),
+            Err(AuthError::InvalidToken)
+        ));
+    }
+
+    #[test]
+    fn accepts_url_encoded_cookie() {
+        let config = AuthConfig::for_tests(SECRET);
+        let cookies = CookieJar::from_headers(&cookie_headers(COOKIE_VALUE));
+        let token = extract_cookie_token(&cookies, &config).unwrap();
+        assert_eq!(token.token, TOKEN);
+        assert_eq!(
+            token.cookie_name.as_deref(),
+            Some("better-auth.session_token")
+        );
+    }
+
+    #[test]
+    fn accepts_url_encoded_bearer_token() {
+        let config = AuthConfig::for_tests(SECRET);
+        assert_eq!(extract_bearer_token(COOKIE_VALUE, &config).unwrap(), TOKEN);
+        assert_eq!(extract_bearer_token(SIGNED, &config).unwrap(), TOKEN);
+    }
+
+    fn session(expires_at: DateTime<Utc>) -> sessions::Model {
+        let now = Utc::now().fixed_offset();
+        sessions::Model {
+            id: "session-1".into(),
+            user_id: "user-1".into(),
+            token: TOKEN.into(),
+            expires_at: expires_at.fixed_offset(),
+            ip_address: None,
+            user_agent: None,
+            created_at: now,
+            updated_at: now,
+            impersonated_by: None,
+        }
+    }
+
+    fn user() -> users::Model {
+        let now = Utc::now().fixed_offset();
+        users::Model {
+            id: "user-1".into(),
+            name: "User 1".into(),
+            email: "user1@example.com".into(),
+            email_verified: true,
+            image: None,
+            created_at: now,
+            updated_at: now,
+            deleted_at: None,
+            role: None,
+            banned: None,
+            ban_reason: None,
+            ban_expires: None,
+        }
+    }
+
+    /// モック DB の状態（クエリ結果は呼ばれる順に返る）
+    fn state(db: MockDatabase) -> AppState {
+        AppState::for_tests(db.into_connection(), AuthConfig::for_tests(SECRET))
+    }
+
+    /// RequireAuth で認証するルートにリクエストを送る
+    async fn send_with_extractor(state: AppState, request: Request) -> Response {
+        async fn handler(RequireAuth(user): RequireAuth) -> String {
+            user.id
+        }
+        Router::new()
+            .route("/", get(handler))
+            .layer(axum::middleware::from_fn(session_cookie_middleware))
+            .with_state(state)
+            .oneshot(request)
+            .await
+            .unwrap()
+    }
+
+    fn request(cookies: &[(&str, &str)]) -> Request {
+        let cookie = cookies
+            .iter()
+            .map(|(name, value)| format!("{name}={value}"))
+            .collect::<Vec<_>>()
+            .join("; ");
+        Request::builder()
+            .uri("/")
+            .header(header::COOKIE, cookie)
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    fn set_cookies(response: &Response) -> Vec<String> {
+        response
+            .headers()
+            .get_all(header::SET_COOKIE)
+            .iter()
+            .map(|value| value.to_str().unwrap().to_string())
+            .collect()
+    }
+
+    /// updateAge（1日）を過ぎたセッション
+    fn stale_session() -> sessions::Model {
+        session(Utc::now() + chrono::Duration::days(5))
+    }
+
+    #[tokio::test]
+    async fn refresh_reissues_session_cookie() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![stale_session()]])
+            .append_query_results([vec![user()]])
+            .append_exec_results([MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 1,
+            }]);
+
+        let response = send_with_extractor(
+            state(db),
+            request(&[("better-auth.session_token", COOKIE_VALUE)]),
+        )
+        .await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let cookies = set_cookies(&response);
+        assert_eq!(cookies.len(), 1);
+        let cookie = Cookie::parse_encoded(cookies[0].as_str()).unwrap();
+        assert_eq!(cookie.name(), "better-auth.session_token");
+        assert_eq!(cookie.value(), SIGNED);
+        assert_eq!(cookie.max_age(), Some(time::Duration::days(7)));
+    }
+
+    #[tokio::test]
+    async fn fresh_session_does_not_reissue_cookie() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![session(Utc::now() + chrono::Duration::days(7))]])
+            .append_query_results([vec![user()]]);
+
+        let response = send_with_extractor(
+            state(db),
+            request(&[("better-auth.session_token", COOKIE_VALUE)]),
+        )
+        .await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(set_cookies(&response).is_empty());
+    }
+
+    #[tokio::test]
+    async fn dont_remember_skips_refresh() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![stale_session()]])
+            .append_query_results([vec![user()]]);
+        let dont_remember = sign_value("true", SECRET);
+        let state = state(db);
+        let db = Arc::clone(&state.db);
+
+        let response = send_with_extractor(
+            state,
+            request(&[
+                ("better-auth.session_token", COOKIE_VALUE),
+                ("better-auth.dont_remember", &dont_remember),
+            ]),
+        )
+        .await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(set_cookies(&response).is_empty());
+        // セッションとユーザーの SELECT のみで、延長の UPDATE は実行されない
+        let db = Arc::try_unwrap(db).ok().unwrap();
+        assert_eq!(db.into_transaction_log().len(), 2);
+    }
+
+    #[test]
+    fn dont_remember_cookie_must_be_signed_true() {
+        let config = AuthConfig::for_tests(SECRET);
+        let jar = |value: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(
+                header::COOKIE,
+                format!("better-auth.dont_remember={value}")
+                    .parse()
+                    .unwrap(),
+            );
+            CookieJar::from_headers(&headers)
+        };
+
+        assert!(has_dont_remember_cookie(
+            &jar(&sign_value("true", SECRET)),
+            &config
+        ));
+        assert!(!has_dont_remember_cookie(&jar("true"), &config));
+        assert!(!has_dont_remember_cookie(
+            &jar(&sign_value("true", OTHER_SECRET)),
+            &config
+        ));
+        assert!(!has_dont_remember_cookie(
+            &jar(&sign_value("false", SECRET)),
+            &config
+        ));
+    }
+
+    #[tokio::test]
+    async fn handler_cookie_takes_precedence_over_refresh() {
+        async fn handler(Extension(refreshed): Extension<RefreshedSessionCookie>) -> CookieJar {
+            refreshed.set(Cookie::new("better-auth.session_token", "refreshed"));
+            CookieJar::new().add(removal_cookie("better-auth.session_token"))
+        }
+        let response = Router::new()
+            .route("/", get(handler))
+            .layer(axum::middleware::from_fn(session_cookie_middleware))
+            .oneshot(request(&[]))
+            .await
+            .unwrap();
+
+        let cookies = set_cookies(&response);
+        assert_eq!(cookies.len(), 1);
+        assert!(cookies[0].starts_with("better-auth.session_token=;"));
+    }
+
+    #[test]
+    fn rejects_unsigned_bearer_token_by_default() {
+        let mut config = AuthConfig::for_tests(SECRET);
+        assert!(matches!(
+            extract_bearer_token(TOKEN, &config),
+            Err(AuthError::InvalidToken)
+        ));
+
+        config.bearer_allow_unsigned = true;
+        assert_eq!(extract_bearer_token(TOKEN, &config).unwrap(), TOKEN);
+        assert!(matches!(
+            extract_bearer_token("", &config),
+            Err(AuthError::InvalidToken)
+        ));
+    }
+
+    // ------------------------------------------------------------
+    // middleware / RequireAuth / OptionalAuthUser で認証結果が一致すること
+    // ------------------------------------------------------------
+
+    /// 認証の入力とモック DB の状態
+    struct AuthCase {
+        name: &'static str,
+        cookie: Option<&'static str>,
+        db: fn() -> MockDatabase,
+        /// 失敗する場合のステータスとエラーコード（None は成功）
+        rejection: Option<(StatusCode, &'static str)>,
+    }
+
+    fn auth_cases() -> Vec<AuthCase> {
+        vec![
+            AuthCase {
+                name: "valid",
+                cookie: Some(COOKIE_VALUE),
+                db: || {
+                    MockDatabase::new(DatabaseBackend::Postgres)
+                        .append_query_results([vec![session(
+                            Utc::now() + chrono::Duration::days(7),
+                        )]])
+                        .append_query_results([vec![user()]])
+                },
+                rejection: None,
+            },
+            AuthCase {
+                name: "missing cookie",
+                cookie: None,
+                db: || MockDatabase::new(DatabaseBackend::Postgres),
+                rejection: Some((StatusCode::UNAUTHORIZED, "UNAUTHORIZED")),
+            },
+            AuthCase {
+                name: "bad signature",
+                cookie: Some(SIGNED_WITH_OTHER_SECRET),
+                db: || MockDatabase::new(DatabaseBackend::Postgres),
+                rejection: Some((StatusCode::UNAUTHORIZED, "INVALID_TOKEN")),
+            },
+            AuthCase {
+                name: "expired",
+                cookie: Some(COOKIE_VALUE),
+                db: || {
+                    MockDatabase::new(DatabaseBackend::Postgres).append_query_results([vec![
+                        session(Utc::now() - chrono::Duration::hours(1)),
+                    ]])
+                },
+                rejection: Some((StatusCode::UNAUTHORIZED, "SESSION_EXPIRED")),
+            },
+            AuthCase {
+                name: "banned",
+                cookie: Some(COOKIE_VALUE),
+                db: || {
+                    MockDatabase::new(DatabaseBackend::Postgres)
+                        .append_query_results([vec![session(
+                            Utc::now() + chrono::Duration::days(7),
+                        )]])
+                        .append_query_results([vec![users::Model {
+                            banned: Some(true),
+                            ban_reason: Some("spam".into()),
+                            ..user()
+                        }]])
+                },
+                rejection: Some((StatusCode::FORBIDDEN, "USER_BANNED")),
+            },
+        ]
+    }
+
+    impl AuthCase {
+        async fn send(&self, router: fn(AppState) -> Router) -> (StatusCode, serde_json::Value) {
+            let cookies: Vec<_> = self
+                .cookie
+                .map(|value| ("better-auth.session_token", value))
+                .into_iter()
+                .collect();
+            let response = router(state((self.db)()))
+                .oneshot(request(&cookies))
+                .await
+                .unwrap();
+
+            let status = response.status();
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            let body = serde_json::from_slice(&body).unwrap_or(serde_json::Value::Null);
+            (status, body)
+        }
+    }
+
+    fn middleware_router(state: AppState) -> Router {
+        Router::new()
+            .route("/", get(|| async { "authenticated" }))
+            .route_layer(axum::middleware::from_fn_with_state(
+                state.clone(),
+                auth_middleware,
+            ))
+            .with_state(state)
+    }
+
+    fn extractor_router(state: AppState) -> Router {
+        Router::new()
+            .route(
+                "/",
+                get(|RequireAuth(_): RequireAuth| async { "authenticated" }),
+            )
+            .with_state(state)
+    }
+
+    fn optional_router(state: AppState) -> Router {
+        Router::new()
+            .route(
+                "/",
+                get(|OptionalAuthUser(user): OptionalAuthUser| async move {
+                    Json(serde_json::json!({ "authenticated": user.is_some() }))
+                }),
+            )
+            .with_state(state)
+    }
+
+    #[tokio::test]
+    async fn middleware_and_extractors_authenticate_alike() {
+        for case in auth_cases() {
+            let via_middleware = case.send(middleware_router).await;
+            let via_extractor = case.send(extractor_router).await;
+            let (optional_status, optional_body) = case.send(optional_router).await;
+
+            assert_eq!(via_middleware, via_extractor, "{}", case.name);
+            match case.rejection {
+                Some((status, code)) => {
+                    assert_eq!(via_middleware.0, status, "{}", case.name);
+                    assert_eq!(via_middleware.1["error"]["code"], code, "{}", case.name);
+                }
+                None => assert_eq!(via_middleware.0, StatusCode::OK, "{}", case.name),
+            }
+
+            // 任意認証は失敗してもゲストとして続行する
+            assert_eq!(optional_status, StatusCode::OK, "{}", case.name);
+            assert_eq!(
+                optional_body["authenticated"],
+                case.rejection.is_none(),
+                "{}",
+                case.name
+            );
+        }
     }
 }
@@ -1,3 +1,5 @@
 pub mod auth;
+pub mod role;
 
 pub use auth::*;
+pub use role::*;
@@ -0,0 +1,84 @@
+use std::marker::PhantomData;
+
+use axum::{
+    extract::{FromRequestParts, Request, State},
+    http::request::Parts,
+    middleware::Next,
+    response::Response,
+};
+
+use super::auth::{authenticate, AuthError, AuthExtension, AuthUser};
+use crate::AppState;
+
+// ============================================================
+// ロールによるアクセス制御
+// - ルートグループ単位: require_role middleware（routes::routes で指定）
+// - ハンドラ単位: RequireRole<R> extractor
+// - 未ログインは 401、ロール不足は 403（FORBIDDEN）
+// - なりすましセッションは 403（IMPERSONATION_NOT_ALLOWED）。なりすまし中に
+//   対象ユーザーのロールで管理操作（さらになりすましを開始するなど）をさせない
+// ============================================================
+
+/// ロール名を表すマーカー型
+///
+/// 独自ロールを追加する場合:
+/// ```ignore
+/// pub struct Editor;
+/// impl Role for Editor {
+///     const NAME: &'static str = "editor";
+/// }
+/// ```
+pub trait Role {
+    const NAME: &'static str;
+}
+
+/// 管理者ロール（Better Auth の admin プラグインの adminRoles と同じ "admin"）
+pub struct Admin;
+
+impl Role for Admin {
+    const NAME: &'static str = "admin";
+}
+
+/// 指定したロールを持っていなければ InsufficientRole（なりすましセッションは ImpersonationNotAllowed）
+fn ensure_role(user: AuthUser, role: &str) -> Result<AuthUser, AuthError> {
+    user.ensure_not_impersonated()?;
+    if !user.has_role(role) {
+        let err = AuthError::InsufficientRole;
+        err.log();
+        return Err(err);
+    }
+    Ok(user)
+}
+
+/// 指定したロールを要求するミドルウェア
+/// 使い方: middleware::from_fn_with_state((state, "admin"), require_role)
+pub async fn require_role(
+    State((state, role)): State<(AppState, &'static str)>,
+    request: Request,
+    next: Next,
+) -> Result<Response, AuthError> {
+    let (mut parts, body) = request.into_parts();
+    let auth_user = ensure_role(authenticate(&parts, &state).await?, role)?;
+
+    parts.extensions.insert(AuthExtension(auth_user));
+    Ok(next.run(Request::from_parts(parts, body)).await)
+}
+
+/// ロール必須用のラッパー型
+/// 使い方: async fn handler(RequireRole(user, _): RequireRole<Admin>) -> ...
+pub struct RequireRole<R>(pub AuthUser, pub PhantomData<R>);
+
+impl<R> FromRequestParts<AppState> for RequireRole<R>
+where
+    R: Role + Send + Sync,
+{
+    type Rejection = AuthError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let user = ensure_role(authenticate(parts, state).await?, R::NAME)?;
+        Ok(RequireRole(user, PhantomData))
+    }
+}
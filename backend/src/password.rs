@@ -0,0 +1,295 @@
+use std::env;
+use std::sync::Arc;
+
+use axum::{
+    body::Bytes,
+    extract::{FromRequest, Request},
+    http::StatusCode,
+};
+use rand::RngCore;
+use scrypt::{scrypt, Params};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+use serde::{de::DeserializeOwned, Deserialize};
+use subtle::ConstantTimeEq;
+use unicode_normalization::UnicodeNormalization;
+
+use crate::config::ConfigError;
+use crate::entity::accounts;
+use crate::error::ApiError;
+use crate::middleware::{authenticate, AuthUser};
+use crate::AppState;
+
+// ============================================================
+// パスワードハッシュ
+// - PasswordHasher トレイトで実装を差し替え可能
+//   （Better Auth の emailAndPassword.password.hash / verify に相当）
+// - デフォルトは Better Auth 互換の scrypt
+// ============================================================
+
+/// パスワードハッシュの実装
+///
+/// hash / verify は CPU を占有するため、async な処理からは spawn_blocking で呼ぶこと
+pub trait PasswordHasher: Send + Sync {
+    /// accounts.password に保存する形式でハッシュ化
+    fn hash(&self, password: &str) -> String;
+    /// ハッシュとパスワードを照合
+    fn verify(&self, hash: &str, password: &str) -> bool;
+}
+
+/// PASSWORD_HASHER の値から実装を選択（未設定は scrypt）
+///
+/// argon2 / bcrypt は Cargo の feature を有効にした場合のみ利用可能
+pub fn hasher_from_env() -> Result<Arc<dyn PasswordHasher>, ConfigError> {
+    hasher(&env::var("PASSWORD_HASHER").unwrap_or_else(|_| "scrypt".into()))
+}
+
+fn hasher(name: &str) -> Result<Arc<dyn PasswordHasher>, ConfigError> {
+    match name {
+        "scrypt" => Ok(Arc::new(ScryptHasher)),
+        #[cfg(feature = "argon2")]
+        "argon2" => Ok(Arc::new(Argon2Hasher)),
+        #[cfg(feature = "bcrypt")]
+        "bcrypt" => Ok(Arc::new(BcryptHasher::default())),
+        other => Err(ConfigError::new(
+            "PASSWORD_HASHER",
+            format!(
+                "must be scrypt, or argon2 / bcrypt with the Cargo feature of the same name (got {other:?})"
+            ),
+        )),
+    }
+}
+
+// ============================================================
+// scrypt（Better Auth 互換）
+// - accounts.password に保存される "{salt}:{key}" 形式（いずれも hex）
+// - scrypt(N=16384, r=16, p=1, dkLen=64)、salt は hex 文字列をそのまま使う
+// - パスワードは NFKC 正規化してからハッシュ化する
+// ============================================================
+
+/// scrypt の log2(N)（N = 16384）
+const SCRYPT_LOG_N: u8 = 14;
+const SCRYPT_R: u32 = 16;
+const SCRYPT_P: u32 = 1;
+const SCRYPT_KEY_LEN: usize = 64;
+const SCRYPT_SALT_LEN: usize = 16;
+
+pub struct ScryptHasher;
+
+impl ScryptHasher {
+    fn derive_key(password: &str, salt: &str) -> Option<Vec<u8>> {
+        let params = Params::new(SCRYPT_LOG_N, SCRYPT_R, SCRYPT_P, SCRYPT_KEY_LEN).ok()?;
+        let password: String = password.nfkc().collect();
+
+        let mut key = vec![0u8; SCRYPT_KEY_LEN];
+        scrypt(password.as_bytes(), salt.as_bytes(), &params, &mut key).ok()?;
+        Some(key)
+    }
+}
+
+impl PasswordHasher for ScryptHasher {
+    fn hash(&self, password: &str) -> String {
+        let mut salt = [0u8; SCRYPT_SALT_LEN];
+        rand::thread_rng().fill_bytes(&mut salt);
+        let salt = hex::encode(salt);
+
+        // パラメータは定数のため失敗しない
+        let key = Self::derive_key(password, &salt).expect("scrypt parameters must be valid");
+        format!("{}:{}", salt, hex::encode(key))
+    }
+
+    fn verify(&self, hash: &str, password: &str) -> bool {
+        let Some((salt, key)) = hash.split_once(':') else {
+            return false;
+        };
+        let Ok(expected) = hex::decode(key) else {
+            return false;
+        };
+        let Some(derived) = Self::derive_key(password, salt) else {
+            return false;
+        };
+
+        derived.ct_eq(&expected).into()
+    }
+}
+
+// ============================================================
+// argon2 / bcrypt（feature で有効化）
+// - Better Auth 側でも同じアルゴリズムを hash / verify に設定すること
+// ============================================================
+
+#[cfg(feature = "argon2")]
+pub struct Argon2Hasher;
+
+#[cfg(feature = "argon2")]
+impl PasswordHasher for Argon2Hasher {
+    fn hash(&self, password: &str) -> String {
+        use argon2::password_hash::{rand_core::OsRng, PasswordHasher as _, SaltString};
+
+        let salt = SaltString::generate(&mut OsRng);
+        argon2::Argon2::default()
+            .hash_password(password.as_bytes(), &salt)
+            .expect("argon2 parameters must be valid")
+            .to_string()
+    }
+
+    fn verify(&self, hash: &str, password: &str) -> bool {
+        use argon2::password_hash::{PasswordHash, PasswordVerifier as _};
+
+        PasswordHash::new(hash).is_ok_and(|parsed| {
+            argon2::Argon2::default()
+                .verify_password(password.as_bytes(), &parsed)
+                .is_ok()
+        })
+    }
+}
+
+#[cfg(feature = "bcrypt")]
+pub struct BcryptHasher {
+    pub cost: u32,
+}
+
+#[cfg(feature = "bcrypt")]
+impl Default for BcryptHasher {
+    fn default() -> Self {
+        Self {
+            cost: bcrypt::DEFAULT_COST,
+        }
+    }
+}
+
+#[cfg(feature = "bcrypt")]
+impl PasswordHasher for BcryptHasher {
+    fn hash(&self, password: &str) -> String {
+        bcrypt::hash(password, self.cost).expect("bcrypt cost must be valid")
+    }
+
+    fn verify(&self, hash: &str, password: &str) -> bool {
+        bcrypt::verify(password, hash).unwrap_or(false)
+    }
+}
+
+// ============================================================
+// 現在のパスワードの再確認（機密性の高い操作用）
+// - リクエストボディの "confirmPassword" を credential アカウントのハッシュと照合
+// - credential アカウントがない（Google 認証のみの）ユーザーは確認不要
+// - ボディの他のフィールドは T として受け取れる
+// ============================================================
+
+/// confirmPassword 以外のフィールドを持たないボディ
+#[derive(Deserialize, Default)]
+pub struct NoBody {}
+
+#[derive(Deserialize)]
+struct ConfirmPasswordBody<T> {
+    #[serde(rename = "confirmPassword")]
+    confirm_password: Option<String>,
+    #[serde(flatten)]
+    inner: T,
+}
+
+/// パスワード再確認済みのユーザーとリクエストボディ
+/// 使い方: async fn handler(ConfirmedPassword(user, body): ConfirmedPassword<MyBody>) -> ...
+pub struct ConfirmedPassword<T = NoBody>(pub AuthUser, pub T);
+
+impl<T> FromRequest<AppState> for ConfirmedPassword<T>
+where
+    T: DeserializeOwned + Send,
+{
+    type Rejection = ApiError;
+
+    async fn from_request(request: Request, state: &AppState) -> Result<Self, Self::Rejection> {
+        let (parts, body) = request.into_parts();
+        let user = authenticate(&parts, state).await?;
+
+        let bytes = Bytes::from_request(Request::from_parts(parts, body), state)
+            .await
+            .map_err(|_| ApiError::validation("Failed to read request body"))?;
+
+        // ボディなし（Google 認証ユーザーなど）は {} として扱う
+        let bytes = if bytes.is_empty() {
+            Bytes::from_static(b"{}")
+        } else {
+            bytes
+        };
+        let body: ConfirmPasswordBody<T> = serde_json::from_slice(&bytes)
+            .map_err(|e| ApiError::validation(format!("Invalid request body: {e}")))?;
+
+        let credential = accounts::Entity::find()
+            .filter(accounts::Column::UserId.eq(&user.id))
+            .filter(accounts::Column::ProviderId.eq("credential"))
+            .one(state.db.as_ref())
+            .await?;
+
+        if let Some(hash) = credential.and_then(|account| account.password) {
+            let password = body
+                .confirm_password
+                .ok_or_else(|| ApiError::validation("confirmPassword is required"))?;
+
+            // scrypt などは重いためブロッキングスレッドで実行
+            let hasher = state.password_hasher.clone();
+            let valid = tokio::task::spawn_blocking(move || hasher.verify(&hash, &password))
+                .await
+                .map_err(|e| {
+                    tracing::error!("Password verification task failed: {}", e);
+                    ApiError::internal("Internal server error")
+                })?;
+
+            if !valid {
+                return Err(ApiError::new(
+                    StatusCode::BAD_REQUEST,
+                    "INVALID_PASSWORD",
+                    "パスワードが正しくありません",
+                ));
+            }
+        }
+
+        Ok(ConfirmedPassword(user, body.inner))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Better Auth の hashPassword と同じ方法（scrypt N=16384, r=16, p=1, dkLen=64、
+    // salt は 16 バイトの hex 文字列をそのまま使う）で "password123" をハッシュ化したフィクスチャ
+    const BETTER_AUTH_HASH: &str = "8f2c1a9e4b7d3f6a0c5e9b2d1f4a7c3e:e7c9a19b51e4836735a886638baafe2911fad53b1cee60c8b2a4d80a4f6106aca657cc2c3beecbdd144ac8fe62c904d06d5c9b8f3106ce47716b84a9a97306b7";
+
+    #[test]
+    fn verifies_better_auth_hash() {
+        assert!(ScryptHasher.verify(BETTER_AUTH_HASH, "password123"));
+        assert!(!ScryptHasher.verify(BETTER_AUTH_HASH, "password124"));
+    }
+
+    #[test]
+    fn normalizes_password_with_nfkc() {
+        // 全角文字は NFKC 正規化で半角と同じパスワードになる
+        assert!(ScryptHasher.verify(BETTER_AUTH_HASH, "ｐａｓｓｗｏｒｄ１２３"));
+    }
+
+    #[test]
+    fn rejects_unsupported_hasher() {
+        assert!(hasher("scrypt").is_ok());
+
+        let error = hasher("md5").err().unwrap();
+        assert_eq!(error.var, "PASSWORD_HASHER");
+    }
+
+    #[test]
+    fn hash_round_trip() {
+        let hash = ScryptHasher.hash("correct horse battery staple");
+
+        let (salt, key) = hash.split_once(':').unwrap();
+        assert_eq!(salt.len(), SCRYPT_SALT_LEN * 2);
+        assert_eq!(key.len(), SCRYPT_KEY_LEN * 2);
+        assert!(ScryptHasher.verify(&hash, "correct horse battery staple"));
+        assert!(!ScryptHasher.verify(&hash, "correct horse battery"));
+    }
+
+    #[test]
+    fn rejects_malformed_hash() {
+        assert!(!ScryptHasher.verify("", "password123"));
+        assert!(!ScryptHasher.verify("8f2c1a9e4b7d3f6a0c5e9b2d1f4a7c3e", "password123"));
+        assert!(!ScryptHasher.verify("8f2c1a9e4b7d3f6a0c5e9b2d1f4a7c3e:not-hex", "password123"));
+    }
+}
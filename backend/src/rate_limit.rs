@@ -0,0 +1,139 @@
+use std::net::{IpAddr, SocketAddr};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::http::HeaderMap;
+use moka::sync::Cache;
+
+// ============================================================
+// レート制限（インスタンスごとのメモリ上のカウンタ）
+// - key（メールアドレス・IP など）ごとに、最初のリクエストから window の間の回数を数える
+// - 複数インスタンスで動かす場合、上限はインスタンスごとに適用される
+// ============================================================
+
+/// 保持する key の上限（超えた分は古いものから破棄）
+const MAX_KEYS: u64 = 100_000;
+
+pub struct RateLimiter {
+    hits: Cache<String, Arc<AtomicU32>>,
+    max_requests: u32,
+}
+
+impl RateLimiter {
+    pub fn new(max_requests: u32, window: Duration) -> Self {
+        Self {
+            hits: Cache::builder()
+                .max_capacity(MAX_KEYS)
+                .time_to_live(window)
+                .build(),
+            max_requests,
+        }
+    }
+
+    /// key のリクエストを記録し、上限以内なら true
+    pub fn check(&self, key: &str) -> bool {
+        let counter = self
+            .hits
+            .get_with(key.to_string(), || Arc::new(AtomicU32::new(0)));
+        counter.fetch_add(1, Ordering::Relaxed) < self.max_requests
+    }
+}
+
+/// レート制限に使うクライアントの IP
+/// - trusted_proxy_hops が 0 の場合は接続元の IP（X-Forwarded-For はクライアントが自由に設定できるため使わない）
+/// - 1 以上の場合は X-Forwarded-For の右から trusted_proxy_hops 番目
+///   （信頼できるプロキシが追加した値。それより左はクライアントが設定できる）
+pub fn client_ip(
+    headers: &HeaderMap,
+    peer: Option<SocketAddr>,
+    trusted_proxy_hops: usize,
+) -> Option<IpAddr> {
+    let peer_ip = peer.map(|addr| addr.ip());
+    if trusted_proxy_hops == 0 {
+        return peer_ip;
+    }
+
+    let forwarded: Vec<&str> = headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .collect();
+
+    forwarded
+        .len()
+        .checked_sub(trusted_proxy_hops)
+        .and_then(|i| forwarded[i].parse().ok())
+        .or(peer_ip)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limits_requests_per_key() {
+        let limiter = RateLimiter::new(2, Duration::from_secs(60));
+
+        assert!(limiter.check("a"));
+        assert!(limiter.check("a"));
+        assert!(!limiter.check("a"));
+        // key ごとに数える
+        assert!(limiter.check("b"));
+    }
+
+    #[test]
+    fn resets_after_window() {
+        let limiter = RateLimiter::new(1, Duration::from_millis(50));
+
+        assert!(limiter.check("a"));
+        assert!(!limiter.check("a"));
+        std::thread::sleep(Duration::from_millis(100));
+        assert!(limiter.check("a"));
+    }
+
+    fn forwarded(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-forwarded-for", value.parse().unwrap());
+        headers
+    }
+
+    const PEER: &str = "10.0.0.1:50000";
+
+    #[test]
+    fn ignores_forwarded_for_without_trusted_proxies() {
+        let peer = PEER.parse().ok();
+        assert_eq!(
+            client_ip(&forwarded("203.0.113.7"), peer, 0),
+            Some("10.0.0.1".parse().unwrap())
+        );
+    }
+
+    #[test]
+    fn uses_address_added_by_trusted_proxies() {
+        let peer = PEER.parse().ok();
+        // 先頭はクライアントが設定した値。信頼できるプロキシが追加した右端を使う
+        let headers = forwarded("198.51.100.1, 203.0.113.7");
+        assert_eq!(
+            client_ip(&headers, peer, 1),
+            Some("203.0.113.7".parse().unwrap())
+        );
+
+        let headers = forwarded("198.51.100.1, 203.0.113.7, 10.0.0.2");
+        assert_eq!(
+            client_ip(&headers, peer, 2),
+            Some("203.0.113.7".parse().unwrap())
+        );
+    }
+
+    #[test]
+    fn falls_back_to_peer_when_forwarded_for_is_short_or_invalid() {
+        let peer = PEER.parse().ok();
+        let expected = Some("10.0.0.1".parse().unwrap());
+        assert_eq!(client_ip(&HeaderMap::new(), peer, 1), expected);
+        assert_eq!(client_ip(&forwarded("203.0.113.7"), peer, 2), expected);
+        assert_eq!(client_ip(&forwarded("not-an-ip"), peer, 1), expected);
+    }
+}
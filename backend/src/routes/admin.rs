@@ -0,0 +1,866 @@
+use std::collections::HashMap;
+
+use axum::{
+    extract::{Extension, Path, Query, State},
+    http::StatusCode,
+    routing::{delete, get, post},
+    Json, Router,
+};
+use axum_extra::extract::CookieJar;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use chrono::{DateTime, Days, FixedOffset, NaiveDate, SecondsFormat, SubsecRound, Utc};
+use sea_orm::{
+    sea_query::{self, extension::postgres::PgExpr, Expr},
+    ActiveModelTrait,
+    ActiveValue::Set,
+    ColumnTrait, Condition, ConnectionTrait, EntityTrait, IntoActiveModel, ModelTrait, Order,
+    QueryFilter, QueryOrder, QuerySelect, TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use super::protected::AccountResponse;
+use crate::audit::{self, AuditAction};
+use crate::authz::AccessControl;
+use crate::device::DeviceInfo;
+use crate::entity::{accounts, sessions, users};
+use crate::error::ApiError;
+use crate::geoip::GeoLocation;
+use crate::impersonation;
+use crate::middleware::{parse_roles, sign_value, AuthExtension};
+use crate::session_cache::SessionCache;
+use crate::stats::{self, Stats, StatsRange};
+use crate::withdrawal;
+use crate::AppState;
+
+// ============================================================
+// 管理者 API（/api/admin/*）
+// - routes::routes で admin ロール必須のポリシーを適用
+// ============================================================
+
+#[derive(Serialize)]
+struct SuccessResponse {
+    success: bool,
+}
+
+/// 猶予期間内の退会ユーザーを復元
+async fn restore_user(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<SuccessResponse>, ApiError> {
+    withdrawal::restore(&state, &id).await?;
+    Ok(Json(SuccessResponse { success: true }))
+}
+
+// ============================================================
+// ユーザー一覧・詳細 API（/api/admin/users）
+// - 退会済みユーザーも含めて検索できる（deleted で絞り込み）
+// - ページングはカーソル方式（並び順の値 + id をカーソルにし、件数が変わってもずれない）
+// - トークン・パスワードはレスポンスに含めない
+// ============================================================
+
+const DEFAULT_PAGE_SIZE: u64 = 20;
+const MAX_PAGE_SIZE: u64 = 100;
+
+/// 並び順の項目
+#[derive(Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum UserSort {
+    #[default]
+    CreatedAt,
+    UpdatedAt,
+    Name,
+    Email,
+}
+
+impl UserSort {
+    fn column(self) -> users::Column {
+        match self {
+            Self::CreatedAt => users::Column::CreatedAt,
+            Self::UpdatedAt => users::Column::UpdatedAt,
+            Self::Name => users::Column::Name,
+            Self::Email => users::Column::Email,
+        }
+    }
+
+    /// カーソルに保存する値（日時は DB と同じマイクロ秒精度）
+    fn cursor_value(self, user: &users::Model) -> String {
+        let timestamp = |t: &DateTime<FixedOffset>| t.to_rfc3339_opts(SecondsFormat::Micros, true);
+        match self {
+            Self::CreatedAt => timestamp(&user.created_at),
+            Self::UpdatedAt => timestamp(&user.updated_at),
+            Self::Name => user.name.clone(),
+            Self::Email => user.email.clone(),
+        }
+    }
+
+    /// カーソルの値を比較用の値に戻す
+    fn parse_cursor_value(self, value: String) -> Option<sea_orm::Value> {
+        match self {
+            Self::CreatedAt | Self::UpdatedAt => {
+                DateTime::parse_from_rfc3339(&value).ok().map(Into::into)
+            }
+            Self::Name | Self::Email => Some(value.into()),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum SortOrder {
+    Asc,
+    #[default]
+    Desc,
+}
+
+impl From<SortOrder> for Order {
+    fn from(order: SortOrder) -> Self {
+        match order {
+            SortOrder::Asc => Order::Asc,
+            SortOrder::Desc => Order::Desc,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct UserListQuery {
+    /// 名前・メールアドレスの部分一致（大文字小文字を区別しない）
+    q: Option<String>,
+    email_verified: Option<bool>,
+    /// true: 退会済みのみ / false: 退会済みを除く / 未指定: すべて
+    deleted: Option<bool>,
+    /// accounts.provider_id（"credential" / "google" など）
+    provider: Option<String>,
+    /// created_at の範囲（RFC 3339。from は含む、to は含まない）
+    created_from: Option<DateTime<FixedOffset>>,
+    created_to: Option<DateTime<FixedOffset>>,
+    #[serde(default)]
+    sort: UserSort,
+    #[serde(default)]
+    order: SortOrder,
+    limit: Option<u64>,
+    /// 前のレスポンスの next_cursor
+    cursor: Option<String>,
+}
+
+/// 最後に返したユーザーの位置（base64url の JSON）
+/// 並び順が変わると位置の意味が変わるため、sort / order も含めて検証する
+#[derive(Deserialize, Serialize)]
+struct UserCursor {
+    sort: UserSort,
+    order: SortOrder,
+    value: String,
+    id: String,
+}
+
+impl UserCursor {
+    fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(self).expect("cursor must be serializable"))
+    }
+
+    fn decode(cursor: &str) -> Option<Self> {
+        let bytes = URL_SAFE_NO_PAD.decode(cursor).ok()?;
+        serde_json::from_slice(&bytes).ok()
+    }
+}
+
+#[derive(Serialize)]
+struct AdminUserResponse {
+    id: String,
+    name: String,
+    email: String,
+    email_verified: bool,
+    image: Option<String>,
+    roles: Vec<String>,
+    /// 連携しているログイン方法（accounts.provider_id）
+    providers: Vec<String>,
+    /// BAN 中かどうか（期限を過ぎた BAN は false）
+    banned: bool,
+    ban_reason: Option<String>,
+    ban_expires: Option<DateTime<FixedOffset>>,
+    created_at: DateTime<FixedOffset>,
+    updated_at: DateTime<FixedOffset>,
+    deleted_at: Option<DateTime<FixedOffset>>,
+}
+
+impl AdminUserResponse {
+    fn new(user: users::Model, providers: Vec<String>) -> Self {
+        Self {
+            roles: parse_roles(user.role.as_deref()),
+            banned: user.banned == Some(true)
+                && user.ban_expires.is_none_or(|expires| expires > Utc::now()),
+            id: user.id,
+            name: user.name,
+            email: user.email,
+            email_verified: user.email_verified,
+            image: user.image,
+            providers,
+            ban_reason: user.ban_reason,
+            ban_expires: user.ban_expires,
+            created_at: user.created_at,
+            updated_at: user.updated_at,
+            deleted_at: user.deleted_at,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct UserListResponse {
+    users: Vec<AdminUserResponse>,
+    /// 次のページがない場合は null
+    next_cursor: Option<String>,
+}
+
+#[derive(Serialize)]
+struct AdminSessionResponse {
+    id: String,
+    ip_address: Option<String>,
+    user_agent: Option<String>,
+    device: DeviceInfo,
+    location: Option<GeoLocation>,
+    /// 有効期限内かどうか
+    active: bool,
+    /// なりすましセッションの場合、開始した管理者のユーザー ID
+    impersonated_by: Option<String>,
+    created_at: DateTime<FixedOffset>,
+    updated_at: DateTime<FixedOffset>,
+    expires_at: DateTime<FixedOffset>,
+}
+
+#[derive(Serialize)]
+struct UserDetailResponse {
+    #[serde(flatten)]
+    user: AdminUserResponse,
+    sessions: Vec<AdminSessionResponse>,
+    accounts: Vec<AccountResponse>,
+}
+
+/// LIKE のワイルドカードをエスケープ
+fn like_pattern(q: &str) -> String {
+    let escaped = q
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("%{escaped}%")
+}
+
+/// ユーザー一覧（検索・絞り込み・カーソルページング）
+async fn list_users(
+    State(state): State<AppState>,
+    Query(query): Query<UserListQuery>,
+) -> Result<Json<UserListResponse>, ApiError> {
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    if !(1..=MAX_PAGE_SIZE).contains(&limit) {
+        return Err(ApiError::validation(format!(
+            "limit must be between 1 and {MAX_PAGE_SIZE}"
+        )));
+    }
+
+    let mut select = users::Entity::find();
+
+    if let Some(q) = query.q.as_deref().map(str::trim).filter(|q| !q.is_empty()) {
+        let pattern = like_pattern(q);
+        select = select.filter(
+            Condition::any()
+                .add(Expr::col(users::Column::Name).ilike(pattern.clone()))
+                .add(Expr::col(users::Column::Email).ilike(pattern)),
+        );
+    }
+    if let Some(email_verified) = query.email_verified {
+        select = select.filter(users::Column::EmailVerified.eq(email_verified));
+    }
+    match query.deleted {
+        Some(true) => select = select.filter(users::Column::DeletedAt.is_not_null()),
+        Some(false) => select = select.filter(users::Column::DeletedAt.is_null()),
+        None => {}
+    }
+    if let Some(provider) = &query.provider {
+        select = select.filter(
+            users::Column::Id.in_subquery(
+                sea_query::Query::select()
+                    .column(accounts::Column::UserId)
+                    .from(accounts::Entity)
+                    .and_where(accounts::Column::ProviderId.eq(provider))
+                    .to_owned(),
+            ),
+        );
+    }
+    if let Some(from) = query.created_from {
+        select = select.filter(users::Column::CreatedAt.gte(from));
+    }
+    if let Some(to) = query.created_to {
+        select = select.filter(users::Column::CreatedAt.lt(to));
+    }
+
+    // 同じ値のユーザーがいても順序が決まるよう、id を第2キーにする
+    let column = query.sort.column();
+    if let Some(cursor) = &query.cursor {
+        let invalid = || ApiError::validation("Invalid cursor");
+        let cursor = UserCursor::decode(cursor).ok_or_else(invalid)?;
+        if cursor.sort != query.sort || cursor.order != query.order {
+            return Err(ApiError::validation("cursor does not match sort and order"));
+        }
+        let value = query
+            .sort
+            .parse_cursor_value(cursor.value)
+            .ok_or_else(invalid)?;
+
+        let after = match query.order {
+            SortOrder::Asc => Condition::any().add(column.gt(value.clone())).add(
+                Condition::all()
+                    .add(column.eq(value))
+                    .add(users::Column::Id.gt(cursor.id)),
+            ),
+            SortOrder::Desc => Condition::any().add(column.lt(value.clone())).add(
+                Condition::all()
+                    .add(column.eq(value))
+                    .add(users::Column::Id.lt(cursor.id)),
+            ),
+        };
+        select = select.filter(after);
+    }
+
+    // 次のページの有無を判定するため1件多く取得
+    let mut users = select
+        .order_by(column, query.order.into())
+        .order_by(users::Column::Id, query.order.into())
+        .limit(limit + 1)
+        .all(state.db.as_ref())
+        .await?;
+
+    let next_cursor = if users.len() as u64 > limit {
+        users.truncate(limit as usize);
+        users.last().map(|last| {
+            UserCursor {
+                sort: query.sort,
+                order: query.order,
+                value: query.sort.cursor_value(last),
+                id: last.id.clone(),
+            }
+            .encode()
+        })
+    } else {
+        None
+    };
+
+    // ページ内のユーザーの連携アカウントをまとめて取得
+    let ids: Vec<&str> = users.iter().map(|u| u.id.as_str()).collect();
+    let mut providers: HashMap<String, Vec<String>> = HashMap::new();
+    if !ids.is_empty() {
+        let accounts = accounts::Entity::find()
+            .filter(accounts::Column::UserId.is_in(ids))
+            .order_by_asc(accounts::Column::CreatedAt)
+            .all(state.db.as_ref())
+            .await?;
+        for account in accounts {
+            providers
+                .entry(account.user_id)
+                .or_default()
+                .push(account.provider_id);
+        }
+    }
+
+    let users = users
+        .into_iter()
+        .map(|user| {
+            let providers = providers.remove(&user.id).unwrap_or_default();
+            AdminUserResponse::new(user, providers)
+        })
+        .collect();
+
+    Ok(Json(UserListResponse { users, next_cursor }))
+}
+
+/// ユーザー詳細（セッション・連携アカウントを含む）
+async fn get_user(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<UserDetailResponse>, ApiError> {
+    let user = users::Entity::find_by_id(&id)
+        .one(state.db.as_ref())
+        .await?
+        .ok_or_else(user_not_found)?;
+
+    let sessions = user
+        .find_related(sessions::Entity)
+        .order_by_desc(sessions::Column::UpdatedAt)
+        .all(state.db.as_ref())
+        .await?;
+    let accounts = user
+        .find_related(accounts::Entity)
+        .order_by_asc(accounts::Column::CreatedAt)
+        .all(state.db.as_ref())
+        .await?;
+
+    let now = Utc::now();
+    let sessions = sessions
+        .into_iter()
+        .map(|session| AdminSessionResponse {
+            device: DeviceInfo::parse(session.user_agent.as_deref()),
+            location: state.geoip.lookup(session.ip_address.as_deref()),
+            active: session.expires_at > now,
+            impersonated_by: session.impersonated_by,
+            id: session.id,
+            ip_address: session.ip_address,
+            user_agent: session.user_agent,
+            created_at: session.created_at,
+            updated_at: session.updated_at,
+            expires_at: session.expires_at,
+        })
+        .collect();
+
+    let providers = accounts.iter().map(|a| a.provider_id.clone()).collect();
+
+    Ok(Json(UserDetailResponse {
+        user: AdminUserResponse::new(user, providers),
+        sessions,
+        accounts: accounts.into_iter().map(AccountResponse::from).collect(),
+    }))
+}
+
+// ============================================================
+// セッションの強制失効 API
+// - 乗っ取られたアカウントのログアウト、シークレット変更後の全ログアウトなど
+// - 操作は admin_audit_logs に記録する
+// - 自インスタンスのキャッシュは即時に破棄（他インスタンスには NOTIFY で伝わる）
+// ============================================================
+
+#[derive(Serialize)]
+struct RevokeResponse {
+    success: bool,
+    /// 削除したセッション数
+    revoked: u64,
+}
+
+#[derive(Deserialize)]
+struct RevokeBeforeRequest {
+    /// これより前に作成されたセッションを失効（RFC 3339）
+    before: DateTime<FixedOffset>,
+}
+
+fn user_not_found() -> ApiError {
+    ApiError::new(StatusCode::NOT_FOUND, "NOT_FOUND", "User not found")
+}
+
+/// ユーザーの指定したセッションを失効
+async fn revoke_user_session(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthExtension>,
+    Path((user_id, session_id)): Path<(String, String)>,
+) -> Result<Json<RevokeResponse>, ApiError> {
+    let txn = state.db.begin().await?;
+
+    let session = sessions::Entity::delete_many()
+        .filter(sessions::Column::Id.eq(&session_id))
+        .filter(sessions::Column::UserId.eq(&user_id))
+        .exec_with_returning(&txn)
+        .await?
+        .pop()
+        .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, "NOT_FOUND", "Session not found"))?;
+
+    audit::record(
+        &txn,
+        &auth.0.id,
+        AuditAction::SessionRevoke,
+        Some(&user_id),
+        json!({ "session_id": session.id }),
+    )
+    .await?;
+
+    txn.commit().await?;
+
+    state
+        .session_cache
+        .invalidate_session(&SessionCache::key(&session.token));
+
+    Ok(Json(RevokeResponse {
+        success: true,
+        revoked: 1,
+    }))
+}
+
+/// ユーザーの全セッションを失効（強制ログアウト）
+async fn revoke_user_sessions(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthExtension>,
+    Path(user_id): Path<String>,
+) -> Result<Json<RevokeResponse>, ApiError> {
+    let txn = state.db.begin().await?;
+
+    users::Entity::find_by_id(&user_id)
+        .one(&txn)
+        .await?
+        .ok_or_else(user_not_found)?;
+
+    let revoked = sessions::Entity::delete_many()
+        .filter(sessions::Column::UserId.eq(&user_id))
+        .exec(&txn)
+        .await?
+        .rows_affected;
+
+    audit::record(
+        &txn,
+        &auth.0.id,
+        AuditAction::UserSessionsRevoke,
+        Some(&user_id),
+        json!({ "revoked": revoked }),
+    )
+    .await?;
+
+    txn.commit().await?;
+
+    state.session_cache.invalidate_user(&user_id);
+
+    Ok(Json(RevokeResponse {
+        success: true,
+        revoked,
+    }))
+}
+
+/// 指定日時より前に作成された全ユーザーのセッションを失効
+/// 操作した管理者のセッションも対象になる（シークレット変更後は再ログインが必要なため）
+async fn revoke_sessions_before(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthExtension>,
+    Json(body): Json<RevokeBeforeRequest>,
+) -> Result<Json<RevokeResponse>, ApiError> {
+    let txn = state.db.begin().await?;
+
+    let revoked = sessions::Entity::delete_many()
+        .filter(sessions::Column::CreatedAt.lt(body.before))
+        .exec(&txn)
+        .await?
+        .rows_affected;
+
+    audit::record(
+        &txn,
+        &auth.0.id,
+        AuditAction::SessionsRevokeBefore,
+        None,
+        json!({ "before": body.before, "revoked": revoked }),
+    )
+    .await?;
+
+    txn.commit().await?;
+
+    state.session_cache.invalidate_all();
+
+    Ok(Json(RevokeResponse {
+        success: true,
+        revoked,
+    }))
+}
+
+// ============================================================
+// BAN API（Better Auth の admin プラグインの banUser / unbanUser 相当）
+// - BAN 中のユーザーは認証ミドルウェアで 403 USER_BANNED になる
+//   （既存のセッションはデフォルトで残すため、期限後はそのまま利用を再開できる）
+// - 操作は admin_audit_logs に記録する
+// ============================================================
+
+const BAN_REASON_MAX_LENGTH: usize = 500;
+
+#[derive(Deserialize)]
+struct BanRequest {
+    /// ユーザーに表示する理由
+    reason: Option<String>,
+    /// BAN の期間（秒。Better Auth の banExpiresIn と同じ）。未指定は無期限
+    expires_in: Option<i64>,
+    /// true の場合は既存のセッションも削除（ログアウト）
+    #[serde(default)]
+    revoke_sessions: bool,
+}
+
+/// 連携アカウントを含めたユーザー情報
+async fn user_response<C: ConnectionTrait>(
+    db: &C,
+    user: users::Model,
+) -> Result<AdminUserResponse, ApiError> {
+    let providers = user
+        .find_related(accounts::Entity)
+        .order_by_asc(accounts::Column::CreatedAt)
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|a| a.provider_id)
+        .collect();
+    Ok(AdminUserResponse::new(user, providers))
+}
+
+/// ユーザーを BAN
+async fn ban_user(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthExtension>,
+    Path(id): Path<String>,
+    Json(body): Json<BanRequest>,
+) -> Result<Json<AdminUserResponse>, ApiError> {
+    if id == auth.0.id {
+        return Err(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            "CANNOT_BAN_YOURSELF",
+            "You cannot ban yourself",
+        ));
+    }
+
+    let reason = body
+        .reason
+        .map(|r| r.trim().to_string())
+        .filter(|r| !r.is_empty());
+    if reason
+        .as_ref()
+        .is_some_and(|r| r.chars().count() > BAN_REASON_MAX_LENGTH)
+    {
+        return Err(ApiError::validation(format!(
+            "reason must be at most {BAN_REASON_MAX_LENGTH} characters"
+        )));
+    }
+
+    let now = Utc::now().trunc_subsecs(6);
+    let expires_at = match body.expires_in {
+        Some(seconds) => Some(
+            chrono::Duration::try_seconds(seconds)
+                .filter(|d| *d > chrono::Duration::zero())
+                .and_then(|d| now.checked_add_signed(d))
+                .ok_or_else(|| ApiError::validation("expires_in must be a positive number"))?
+                .fixed_offset(),
+        ),
+        None => None,
+    };
+
+    let txn = state.db.begin().await?;
+
+    let user = users::Entity::find_by_id(&id)
+        .filter(users::Column::DeletedAt.is_null())
+        .lock_exclusive()
+        .one(&txn)
+        .await?
+        .ok_or_else(user_not_found)?;
+
+    let mut user = user.into_active_model();
+    user.banned = Set(Some(true));
+    user.ban_reason = Set(reason.clone());
+    user.ban_expires = Set(expires_at);
+    user.updated_at = Set(now.fixed_offset());
+    let user = user.update(&txn).await?;
+
+    let revoked = if body.revoke_sessions {
+        sessions::Entity::delete_many()
+            .filter(sessions::Column::UserId.eq(&user.id))
+            .exec(&txn)
+            .await?
+            .rows_affected
+    } else {
+        0
+    };
+
+    audit::record(
+        &txn,
+        &auth.0.id,
+        AuditAction::UserBan,
+        Some(&user.id),
+        json!({ "reason": reason, "expires_at": expires_at, "revoked": revoked }),
+    )
+    .await?;
+
+    let response = user_response(&txn, user).await?;
+    txn.commit().await?;
+
+    // 他インスタンスには users の更新トリガーの NOTIFY で伝わる
+    state.session_cache.invalidate_user(&id);
+
+    Ok(Json(response))
+}
+
+/// BAN を解除
+async fn unban_user(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthExtension>,
+    Path(id): Path<String>,
+) -> Result<Json<AdminUserResponse>, ApiError> {
+    let txn = state.db.begin().await?;
+
+    let user = users::Entity::find_by_id(&id)
+        .lock_exclusive()
+        .one(&txn)
+        .await?
+        .ok_or_else(user_not_found)?;
+
+    let mut user = user.into_active_model();
+    user.banned = Set(Some(false));
+    user.ban_reason = Set(None);
+    user.ban_expires = Set(None);
+    user.updated_at = Set(Utc::now().trunc_subsecs(6).fixed_offset());
+    let user = user.update(&txn).await?;
+
+    audit::record(
+        &txn,
+        &auth.0.id,
+        AuditAction::UserUnban,
+        Some(&user.id),
+        Value::Null,
+    )
+    .await?;
+
+    let response = user_response(&txn, user).await?;
+    txn.commit().await?;
+
+    state.session_cache.invalidate_user(&id);
+
+    Ok(Json(response))
+}
+
+// ============================================================
+// なりすまし API（impersonation.rs）
+// - 開始は admin ロール必須、終了はなりすまし中のセッション（対象ユーザー）で呼ぶ
+// ============================================================
+
+#[derive(Serialize)]
+struct ImpersonationSessionResponse {
+    id: String,
+    user_id: String,
+    expires_at: DateTime<FixedOffset>,
+}
+
+#[derive(Serialize)]
+struct ImpersonateResponse {
+    session: ImpersonationSessionResponse,
+    /// Bearer 認証用の署名付きトークン（Cookie も設定する）
+    token: String,
+}
+
+#[derive(Serialize)]
+struct StopImpersonatingResponse {
+    success: bool,
+    /// 管理者のセッションに戻したかどうか（false の場合はログアウト状態）
+    restored: bool,
+}
+
+/// 対象ユーザーになりすます
+async fn impersonate_user(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthExtension>,
+    Path(id): Path<String>,
+) -> Result<(CookieJar, Json<ImpersonateResponse>), ApiError> {
+    let impersonation = impersonation::start(&state, &auth.0, &id).await?;
+    let session = impersonation.session;
+
+    Ok((
+        impersonation.cookies,
+        Json(ImpersonateResponse {
+            token: sign_value(&session.token, &state.auth_config.secret),
+            session: ImpersonationSessionResponse {
+                id: session.id,
+                user_id: session.user_id,
+                expires_at: session.expires_at,
+            },
+        }),
+    ))
+}
+
+/// なりすましを終了
+async fn stop_impersonating(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthExtension>,
+    cookies: CookieJar,
+) -> Result<(CookieJar, Json<StopImpersonatingResponse>), ApiError> {
+    let (cookies, restored) = impersonation::stop(&state, &auth.0, &cookies).await?;
+    Ok((
+        cookies,
+        Json(StopImpersonatingResponse {
+            success: true,
+            restored,
+        }),
+    ))
+}
+
+// ============================================================
+// 統計 API（stats.rs）
+// ============================================================
+
+/// from を省略した場合の期間（to を含む日数）
+const STATS_DEFAULT_DAYS: u64 = 30;
+/// 指定できる期間の最大日数
+const STATS_MAX_DAYS: i64 = 366;
+
+#[derive(Deserialize)]
+struct StatsQuery {
+    /// 集計期間（YYYY-MM-DD、UTC、両端を含む）。to のデフォルトは今日
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+}
+
+/// 登録数・メール認証状況・セッション数・退会数の統計
+async fn stats(
+    State(state): State<AppState>,
+    Query(query): Query<StatsQuery>,
+) -> Result<Json<Stats>, ApiError> {
+    let to = query.to.unwrap_or_else(|| Utc::now().date_naive());
+    let from = match query.from {
+        Some(from) => from,
+        None => to
+            .checked_sub_days(Days::new(STATS_DEFAULT_DAYS - 1))
+            .ok_or_else(|| ApiError::validation("Invalid to"))?,
+    };
+    if from > to {
+        return Err(ApiError::validation("from must not be after to"));
+    }
+    if (to - from).num_days() >= STATS_MAX_DAYS {
+        return Err(ApiError::validation(format!(
+            "Range must be at most {STATS_MAX_DAYS} days"
+        )));
+    }
+
+    Ok(Json(
+        stats::collect(&state, &StatsRange { from, to }).await?,
+    ))
+}
+
+/// なりすまし中のユーザー（admin ロールなし）が呼ぶため、routes::routes でログイン必須のみ適用
+pub fn impersonation_routes() -> Router<AppState> {
+    Router::new().route("/stop-impersonating", post(stop_impersonating))
+}
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/users", get(list_users))
+        .route("/users/{id}", get(get_user))
+        .route("/users/{id}/restore", post(restore_user))
+        .route("/stats", get(stats))
+}
+
+/// BAN・BAN の解除（routes::routes で user:ban 権限必須のポリシーを適用）
+pub fn ban_routes() -> Router<AppState> {
+    Router::new()
+        .route("/users/{id}/ban", post(ban_user))
+        .route("/users/{id}/unban", post(unban_user))
+}
+
+/// なりすましの開始（routes::routes で user:impersonate 権限必須のポリシーを適用）
+pub fn impersonate_routes() -> Router<AppState> {
+    Router::new().route("/users/{id}/impersonate", post(impersonate_user))
+}
+
+/// セッションの失効（routes::routes で session:revoke 権限必須のポリシーを適用）
+pub fn session_revocation_routes() -> Router<AppState> {
+    Router::new()
+        .route("/users/{id}/sessions", delete(revoke_user_sessions))
+        .route(
+            "/users/{id}/sessions/{session_id}",
+            delete(revoke_user_session),
+        )
+        .route("/sessions/revoke-before", post(revoke_sessions_before))
+}
+
+// ============================================================
+// 権限定義の参照 API（/api/admin/access-control）
+// - routes::routes で user:set-role 権限必須のポリシーを適用
+// ============================================================
+
+/// ロールごとの権限定義を返す（ロール割り当て画面用）
+async fn access_control(State(state): State<AppState>) -> Json<AccessControl> {
+    Json(state.access_control.as_ref().clone())
+}
+
+pub fn access_control_routes() -> Router<AppState> {
+    Router::new().route("/access-control", get(access_control))
+}
@@ -1,11 +1,54 @@
 use axum::{middleware, Router};
 
-use crate::middleware::auth_middleware;
+use crate::authz::{require_permission, Permission};
+use crate::middleware::{
+    auth_middleware, require_role, session_cookie_middleware, verified_email_middleware,
+};
 use crate::AppState;
 
+mod admin;
 mod protected;
 mod public;
 
+/// ルートグループごとの認証ポリシー
+#[derive(Clone)]
+enum AuthPolicy {
+    /// ログイン必須
+    Authenticated,
+    /// ログイン + メール認証済み必須（未認証は 403 EMAIL_NOT_VERIFIED）
+    EmailVerified,
+    /// ログイン + 指定ロール必須（ロール不足は 403 FORBIDDEN）
+    Role(&'static str),
+    /// ログイン + 指定権限必須（権限不足は 403 FORBIDDEN）
+    Permission(Permission),
+}
+
+/// ルートグループに認証ポリシーのミドルウェアを適用
+fn with_auth_policy(
+    router: Router<AppState>,
+    state: &AppState,
+    policy: AuthPolicy,
+) -> Router<AppState> {
+    match policy {
+        AuthPolicy::Authenticated => router.layer(middleware::from_fn_with_state(
+            state.clone(),
+            auth_middleware,
+        )),
+        AuthPolicy::EmailVerified => router.layer(middleware::from_fn_with_state(
+            state.clone(),
+            verified_email_middleware,
+        )),
+        AuthPolicy::Role(role) => router.layer(middleware::from_fn_with_state(
+            (state.clone(), role),
+            require_role,
+        )),
+        AuthPolicy::Permission(permission) => router.layer(middleware::from_fn_with_state(
+            (state.clone(), permission),
+            require_permission,
+        )),
+    }
+}
+
 pub fn public_routes() -> Router<AppState> {
     public::routes()
 }
@@ -14,13 +57,1474 @@ pub fn protected_routes() -> Router<AppState> {
     protected::routes()
 }
 
+pub fn verified_routes() -> Router<AppState> {
+    protected::verified_routes()
+}
+
+pub fn admin_routes() -> Router<AppState> {
+    admin::routes()
+}
+
 pub fn routes(state: AppState) -> Router<AppState> {
     // 公開 API（認証不要）
     let public = public_routes();
 
     // 保護された API（認証必須）
-    let protected =
-        protected_routes().layer(middleware::from_fn_with_state(state.clone(), auth_middleware));
+    let protected = with_auth_policy(protected_routes(), &state, AuthPolicy::Authenticated);
+
+    // 保護された API（認証 + メール認証済み必須）
+    let verified = with_auth_policy(verified_routes(), &state, AuthPolicy::EmailVerified);
+
+    // 管理者 API（admin ロール必須）
+    let admin = with_auth_policy(admin_routes(), &state, AuthPolicy::Role("admin"));
+
+    // セッションの失効（session:revoke 権限が必要）
+    let session_revocation = with_auth_policy(
+        admin::session_revocation_routes(),
+        &state,
+        AuthPolicy::Permission(Permission::new("session", &["revoke"])),
+    );
+
+    // BAN・BAN の解除（user:ban 権限が必要）
+    let ban = with_auth_policy(
+        admin::ban_routes(),
+        &state,
+        AuthPolicy::Permission(Permission::new("user", &["ban"])),
+    );
+
+    // なりすましの開始（user:impersonate 権限が必要）
+    let impersonate = with_auth_policy(
+        admin::impersonate_routes(),
+        &state,
+        AuthPolicy::Permission(Permission::new("user", &["impersonate"])),
+    );
+
+    // 権限定義の参照（ロールを割り当てられる user:set-role 権限が必要）
+    let access_control = with_auth_policy(
+        admin::access_control_routes(),
+        &state,
+        AuthPolicy::Permission(Permission::new("user", &["set-role"])),
+    );
+
+    // なりすましの終了（なりすまし中は対象ユーザーのセッションのため、ログイン必須のみ）
+    let impersonation = with_auth_policy(
+        admin::impersonation_routes(),
+        &state,
+        AuthPolicy::Authenticated,
+    );
+
+    Router::new()
+        .merge(public)
+        .merge(protected)
+        .merge(verified)
+        .nest(
+            "/admin",
+            admin
+                .merge(session_revocation)
+                .merge(ban)
+                .merge(impersonate)
+                .merge(access_control)
+                .merge(impersonation),
+        )
+        // 有効期限を延長したセッションの Cookie を再発行（全ルートの認証処理で共通）
+        .layer(middleware::from_fn(session_cookie_middleware))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::{
+        body::Body,
+        http::{header, Request, StatusCode},
+    };
+    use chrono::{DateTime, Duration, TimeZone, Utc};
+    use sea_orm::{DatabaseBackend, DatabaseConnection, MockDatabase, MockExecResult, Statement};
+    use serde_json::json;
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::authz::AccessControl;
+    use crate::avatar;
+    use crate::config::AuthConfig;
+    use crate::device::DeviceInfo;
+    use crate::entity::{accounts, sessions, user_withdrawals, users, verifications};
+    use crate::middleware::{sign_value, AuthUser};
+    use crate::password::{PasswordHasher, ScryptHasher};
+    use crate::session_cache::{CachedSession, SessionCache};
+    use crate::storage::MemoryBlobStore;
+    use crate::withdrawal;
+
+    const SECRET: &str = "your-secret-key-at-least-32-characters-long";
+    const TOKEN: &str = "Zr3AAbSX0cK7UQyPz1wLe9T2nH6mJq4V";
+
+    /// 権限を持たない admin ロールと、指定した権限だけを持つ operator ロール
+    fn access_control(operator: serde_json::Value) -> AccessControl {
+        serde_json::from_value(json!({
+            "statements": operator.clone(),
+            "roles": { "admin": {}, "operator": operator },
+        }))
+        .unwrap()
+    }
+
+    /// 認証で読むセッションとユーザー（ハンドラのクエリ結果は呼び出し側で追加する）
+    fn auth_db(role: &str) -> MockDatabase {
+        session_db(role, None)
+    }
+
+    fn session_db(role: &str, impersonated_by: Option<&str>) -> MockDatabase {
+        MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([[sessions::Model {
+                token: TOKEN.into(),
+                impersonated_by: impersonated_by.map(str::to_string),
+                ..session_row("session-1", "user-1")
+            }]])
+            .append_query_results([[users::Model {
+                role: Some(role.into()),
+                ..user_row("user-1")
+            }]])
+    }
+
+    fn user_row(id: &str) -> users::Model {
+        let now = Utc::now().fixed_offset();
+        users::Model {
+            id: id.into(),
+            name: format!("User {id}"),
+            email: format!("{id}@example.com"),
+            email_verified: true,
+            image: None,
+            created_at: now,
+            updated_at: now,
+            deleted_at: None,
+            role: Some("user".into()),
+            banned: None,
+            ban_reason: None,
+            ban_expires: None,
+        }
+    }
+
+    fn session_row(id: &str, user_id: &str) -> sessions::Model {
+        let now = Utc::now().fixed_offset();
+        sessions::Model {
+            id: id.into(),
+            user_id: user_id.into(),
+            token: format!("{id}-token"),
+            expires_at: now + Duration::days(7),
+            ip_address: None,
+            user_agent: None,
+            created_at: now,
+            updated_at: now,
+            impersonated_by: None,
+        }
+    }
+
+    fn exec_result(rows_affected: u64) -> MockExecResult {
+        MockExecResult {
+            last_insert_id: 0,
+            rows_affected,
+        }
+    }
+
+    /// 実行した SQL（リクエストの処理後に呼ぶ）
+    fn executed_sql(db: Arc<DatabaseConnection>) -> Vec<String> {
+        executed_statements(db)
+            .into_iter()
+            .map(|statement| statement.sql)
+            .collect()
+    }
+
+    fn executed_statements(db: Arc<DatabaseConnection>) -> Vec<Statement> {
+        let Ok(db) = Arc::try_unwrap(db) else {
+            panic!("database connection is still in use");
+        };
+        db.into_transaction_log()
+            .iter()
+            .flat_map(|transaction| transaction.statements().to_vec())
+            .collect()
+    }
+
+    /// セッショントークンを Bearer で付けたリクエスト
+    fn authorized(method: &str, uri: &str) -> axum::http::request::Builder {
+        Request::builder().method(method).uri(uri).header(
+            header::AUTHORIZATION,
+            format!("Bearer {}", sign_value(TOKEN, SECRET)),
+        )
+    }
+
+    async fn send(
+        method: &str,
+        uri: &str,
+        access_control: AccessControl,
+        db: MockDatabase,
+    ) -> StatusCode {
+        let request = authorized(method, uri).body(Body::empty()).unwrap();
+        send_request(request, access_control, db).await
+    }
+
+    async fn send_request(
+        request: Request<Body>,
+        access_control: AccessControl,
+        db: MockDatabase,
+    ) -> StatusCode {
+        let mut state = test_state(db);
+        state.access_control = Arc::new(access_control);
+        send_with_state(request, state).await
+    }
+
+    fn test_state(db: MockDatabase) -> AppState {
+        AppState::for_tests(db.into_connection(), AuthConfig::for_tests(SECRET))
+    }
+
+    async fn send_with_state(request: Request<Body>, state: AppState) -> StatusCode {
+        send_json(request, state).await.0
+    }
+
+    async fn send_raw(request: Request<Body>, state: AppState) -> axum::response::Response {
+        routes(state.clone())
+            .with_state(state)
+            .oneshot(request)
+            .await
+            .unwrap()
+    }
+
+    /// ステータスと JSON のレスポンス（JSON でない場合は null）
+    async fn send_json(request: Request<Body>, state: AppState) -> (StatusCode, serde_json::Value) {
+        json_response(send_raw(request, state).await).await
+    }
+
+    async fn json_response(response: axum::response::Response) -> (StatusCode, serde_json::Value) {
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or_default())
+    }
+
+    fn json_request(method: &str, uri: &str, body: serde_json::Value) -> Request<Body> {
+        authorized(method, uri)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn session_revocation_requires_session_revoke_permission() {
+        let access_control = || access_control(json!({ "session": ["revoke"] }));
+
+        // admin ロールでも権限がなければ拒否
+        let status = send(
+            "DELETE",
+            "/admin/users/user-2/sessions",
+            access_control(),
+            auth_db("admin"),
+        )
+        .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        // 権限があれば admin ロールでなくてもハンドラまで届く（対象ユーザーがいないため 404）
+        let status = send(
+            "DELETE",
+            "/admin/users/user-2/sessions",
+            access_control(),
+            auth_db("operator").append_query_results([Vec::<users::Model>::new()]),
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn ban_requires_user_ban_permission() {
+        let access_control = || access_control(json!({ "user": ["ban"] }));
+
+        for uri in ["/admin/users/user-2/ban", "/admin/users/user-2/unban"] {
+            let status = send("POST", uri, access_control(), auth_db("admin")).await;
+            assert_eq!(status, StatusCode::FORBIDDEN, "{uri}");
+        }
+
+        let status = send(
+            "POST",
+            "/admin/users/user-2/unban",
+            access_control(),
+            auth_db("operator").append_query_results([Vec::<users::Model>::new()]),
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn impersonation_requires_user_impersonate_permission() {
+        let access_control = || access_control(json!({ "user": ["impersonate"] }));
+
+        let status = send(
+            "POST",
+            "/admin/users/user-2/impersonate",
+            access_control(),
+            auth_db("admin"),
+        )
+        .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        let status = send(
+            "POST",
+            "/admin/users/user-2/impersonate",
+            access_control(),
+            auth_db("operator").append_query_results([Vec::<users::Model>::new()]),
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn impersonated_sessions_cannot_change_the_account() {
+        let requests = [
+            authorized("PATCH", "/me")
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(r#"{"name":"Renamed"}"#)),
+            authorized("POST", "/me/avatar")
+                .header(header::CONTENT_TYPE, "multipart/form-data; boundary=x")
+                .body(Body::empty()),
+            authorized("DELETE", "/me/sessions/session-2").body(Body::empty()),
+        ];
+
+        for request in requests {
+            let request = request.unwrap();
+            let uri = request.uri().clone();
+            // ハンドラのクエリ結果は用意しない（なりすましの確認で拒否される）
+            let status = send_request(
+                request,
+                AccessControl::default(),
+                session_db("user", Some("admin-1")),
+            )
+            .await;
+            assert_eq!(status, StatusCode::FORBIDDEN, "{uri}");
+        }
+    }
+
+    #[tokio::test]
+    async fn impersonated_sessions_cannot_use_role_or_permission_routes() {
+        // 対象ユーザーのロールが権限を持っていても、なりすまし中は拒否（さらになりすましを開始させない）
+        let access_control = || access_control(json!({ "user": ["impersonate", "ban"] }));
+
+        for (method, uri) in [
+            ("POST", "/admin/users/user-2/impersonate"),
+            ("POST", "/admin/users/user-2/unban"),
+        ] {
+            let status = send(
+                method,
+                uri,
+                access_control(),
+                session_db("operator", Some("admin-1")),
+            )
+            .await;
+            assert_eq!(status, StatusCode::FORBIDDEN, "{uri}");
+        }
+
+        // ロールで保護されたルートも同様
+        let status = send(
+            "GET",
+            "/admin/users",
+            AccessControl::default(),
+            session_db("admin", Some("admin-1")),
+        )
+        .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+    }
+
+    /// file フィールドに data を入れた multipart のアップロード
+    fn avatar_upload(data: &[u8]) -> Request<Body> {
+        let mut body =
+            b"--x\r\nContent-Disposition: form-data; name=\"file\"; filename=\"avatar\"\r\n\r\n"
+                .to_vec();
+        body.extend_from_slice(data);
+        body.extend_from_slice(b"\r\n--x--\r\n");
+        authorized("POST", "/me/avatar")
+            .header(header::CONTENT_TYPE, "multipart/form-data; boundary=x")
+            .body(Body::from(body))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn avatar_upload_stores_thumbnails() {
+        let mut png = Vec::new();
+        image::RgbImage::new(300, 200)
+            .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+
+        let db = auth_db("user").append_exec_results([exec_result(1)]);
+        let store = Arc::new(MemoryBlobStore::default());
+        let mut state = test_state(db);
+        state.blob_store = store.clone();
+
+        let status = send_with_state(avatar_upload(&png), state).await;
+
+        assert_eq!(status, StatusCode::OK);
+        let stored = store.0.lock().unwrap();
+        for size in avatar::THUMBNAIL_SIZES {
+            let data = &stored[&avatar::key("user-1", size)];
+            let image = image::load_from_memory(data).unwrap();
+            assert_eq!((image.width(), image.height()), (size, size));
+        }
+    }
+
+    #[tokio::test]
+    async fn avatar_upload_rejects_unsupported_or_oversized_files() {
+        // ハンドラのクエリ結果は用意しない（保存の前に拒否される）
+        let status = send_request(
+            avatar_upload(b"GIF87a is not enough"),
+            AccessControl::default(),
+            auth_db("user"),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let status = send_request(
+            avatar_upload(b"plain text"),
+            AccessControl::default(),
+            auth_db("user"),
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+        let status = send_request(
+            avatar_upload(&vec![0; avatar::MAX_UPLOAD_BYTES + 128 * 1024]),
+            AccessControl::default(),
+            auth_db("user"),
+        )
+        .await;
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[tokio::test]
+    async fn permission_check_uses_the_roles_of_the_user() {
+        let check = |permissions: serde_json::Value, role: &str| {
+            let mut state = test_state(auth_db(role));
+            state.access_control = Arc::new(access_control(json!({ "user": ["ban", "list"] })));
+            let request = json_request(
+                "POST",
+                "/permissions/check",
+                json!({ "permissions": permissions }),
+            );
+            send_json(request, state)
+        };
+
+        let (status, body) = check(json!({ "user": ["ban"] }), "operator").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, json!({ "success": true }));
+
+        // 一部のアクションしか持っていない場合・ロールに権限がない場合
+        let (_, body) = check(json!({ "user": ["ban", "delete"] }), "operator").await;
+        assert_eq!(body, json!({ "success": false }));
+        let (_, body) = check(json!({ "user": ["ban"] }), "admin").await;
+        assert_eq!(body, json!({ "success": false }));
+
+        // 権限を指定しない場合は 400
+        let (status, body) = check(json!({}), "operator").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"]["code"], "VALIDATION_ERROR");
+        let (status, _) = check(json!({ "user": [] }), "operator").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    fn account_row(user_id: &str, provider_id: &str) -> accounts::Model {
+        let now = Utc::now().fixed_offset();
+        accounts::Model {
+            id: format!("{user_id}-{provider_id}"),
+            user_id: user_id.into(),
+            account_id: format!("{provider_id}-{user_id}"),
+            provider_id: provider_id.into(),
+            access_token: None,
+            refresh_token: None,
+            access_token_expires_at: None,
+            refresh_token_expires_at: None,
+            scope: None,
+            id_token: None,
+            password: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    fn credential_account(user_id: &str, password: &str) -> accounts::Model {
+        accounts::Model {
+            password: Some(ScryptHasher.hash(password)),
+            ..account_row(user_id, "credential")
+        }
+    }
+
+    #[tokio::test]
+    async fn withdraw_soft_deletes_the_user_and_signs_out_everywhere() {
+        let account = credential_account("user-1", "password123");
+        let db = auth_db("user")
+            // パスワードの確認
+            .append_query_results([[account.clone()]])
+            // 復元用のデータ（ユーザー・アカウント）の保存
+            .append_query_results([[user_row("user-1")]])
+            .append_query_results([[account]])
+            .append_query_results([[user_withdrawals::Model {
+                user_id: "user-1".into(),
+                email_hash: "hash".into(),
+                encrypted_data: "data".into(),
+                created_at: Utc::now().fixed_offset(),
+            }]])
+            // users の匿名化、sessions・accounts の削除
+            .append_exec_results([exec_result(1), exec_result(3), exec_result(1)]);
+        let state = test_state(db);
+        let db = state.db.clone();
+
+        let response = send_raw(
+            json_request(
+                "POST",
+                "/user/withdraw",
+                json!({ "confirmPassword": "password123" }),
+            ),
+            state,
+        )
+        .await;
+        let cookies: Vec<_> = response
+            .headers()
+            .get_all(header::SET_COOKIE)
+            .iter()
+            .map(|v| v.to_str().unwrap().to_string())
+            .collect();
+        let (status, body) = json_response(response).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["success"], true);
+        // セッション Cookie を削除する
+        assert!(!cookies.is_empty());
+        assert!(
+            cookies.iter().all(|c| c.contains("Max-Age=0")),
+            "{cookies:?}"
+        );
+
+        let sql = executed_sql(db);
+        let position = |prefix: &str| {
+            sql.iter()
+                .position(|s| s.starts_with(prefix))
+                .unwrap_or_else(|| panic!("{prefix} was not executed: {sql:#?}"))
+        };
+        let begin = position("BEGIN");
+        let backup = position(r#"INSERT INTO "user_withdrawals""#);
+        let anonymize = position(
+            r#"UPDATE "users" SET "email" = $1, "name" = $2, "image" = $3, "deleted_at" = $4"#,
+        );
+        let sessions = position(r#"DELETE FROM "sessions""#);
+        let accounts = position(r#"DELETE FROM "accounts""#);
+        let commit = position("COMMIT");
+        assert!(begin < backup && backup < anonymize && anonymize < sessions);
+        assert!(sessions < accounts && accounts < commit);
+    }
+
+    #[tokio::test]
+    async fn withdraw_requires_the_current_password() {
+        for (body, code) in [
+            (
+                json!({ "confirmPassword": "wrong-password" }),
+                "INVALID_PASSWORD",
+            ),
+            (json!({}), "VALIDATION_ERROR"),
+        ] {
+            // パスワードが確認できなければ、退会の処理（トランザクション）に進まない
+            let db = auth_db("user")
+                .append_query_results([[credential_account("user-1", "password123")]]);
+
+            let (status, response) =
+                send_json(json_request("POST", "/user/withdraw", body), test_state(db)).await;
+
+            assert_eq!(status, StatusCode::BAD_REQUEST);
+            assert_eq!(response["error"]["code"], code);
+        }
+    }
+
+    #[tokio::test]
+    async fn withdraw_does_not_ask_social_login_users_for_a_password() {
+        // credential アカウントがない場合は、ボディなしで退会できる
+        let db = auth_db("user")
+            .append_query_results([Vec::<accounts::Model>::new()])
+            .append_query_results([[user_row("user-1")]])
+            .append_query_results([Vec::<accounts::Model>::new()])
+            .append_query_results([[user_withdrawals::Model {
+                user_id: "user-1".into(),
+                email_hash: "hash".into(),
+                encrypted_data: "data".into(),
+                created_at: Utc::now().fixed_offset(),
+            }]])
+            .append_exec_results([exec_result(1), exec_result(1), exec_result(0)]);
+
+        let status = send_with_state(
+            authorized("POST", "/user/withdraw")
+                .body(Body::empty())
+                .unwrap(),
+            test_state(db),
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn withdraw_fails_when_the_user_was_withdrawn_concurrently() {
+        // 行ロックで読んだ時点で退会済みの場合は、何も変更せずにロールバック
+        let db = auth_db("user")
+            .append_query_results([Vec::<accounts::Model>::new()])
+            .append_query_results([Vec::<users::Model>::new()]);
+        let state = test_state(db);
+        let db = state.db.clone();
+
+        let (status, body) = send_json(
+            authorized("POST", "/user/withdraw")
+                .body(Body::empty())
+                .unwrap(),
+            state,
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body["error"]["code"], "USER_WITHDRAWN");
+        let sql = executed_sql(db);
+        assert!(sql.iter().any(|s| s == "ROLLBACK"), "{sql:#?}");
+        assert!(!sql
+            .iter()
+            .any(|s| s.starts_with("UPDATE") || s.starts_with("DELETE")));
+    }
+
+    /// セッションキャッシュを有効にし、tokens のセッションを入れておく
+    fn with_cached_sessions(state: &mut AppState, tokens: &[&str]) {
+        state.session_cache = SessionCache::new(100, std::time::Duration::from_secs(60));
+        for token in tokens {
+            let user = AuthUser {
+                id: "user-1".into(),
+                name: "User 1".into(),
+                email: "user-1@example.com".into(),
+                email_verified: true,
+                image: None,
+                roles: vec!["user".into()],
+                updated_at: Utc::now().fixed_offset(),
+                session_id: format!("{token}-session"),
+                device: DeviceInfo::parse(None),
+                impersonated_by: None,
+            };
+            state.session_cache.insert(
+                SessionCache::key(token),
+                CachedSession {
+                    user,
+                    expires_at: Utc::now() + Duration::days(1),
+                },
+            );
+        }
+    }
+
+    fn set_cookies(response: &axum::response::Response) -> Vec<String> {
+        response
+            .headers()
+            .get_all(header::SET_COOKIE)
+            .iter()
+            .map(|v| v.to_str().unwrap().to_string())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn revoking_another_session_keeps_the_current_one() {
+        let db = auth_db("user")
+            .append_query_results([[session_row("session-2", "user-1")]])
+            .append_exec_results([exec_result(1)]);
+        let mut state = test_state(db);
+        with_cached_sessions(&mut state, &["session-2-token"]);
+        let cache = state.session_cache.clone();
+        let db = state.db.clone();
+
+        let response = send_raw(
+            authorized("DELETE", "/me/sessions/session-2")
+                .body(Body::empty())
+                .unwrap(),
+            state,
+        )
+        .await;
+        assert!(set_cookies(&response).is_empty());
+        let (status, body) = json_response(response).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, json!({ "success": true, "revoked": 1 }));
+        // 削除したセッションはキャッシュからも破棄する
+        assert!(cache.get(&SessionCache::key("session-2-token")).is_none());
+        let sql = executed_sql(db);
+        assert!(sql
+            .iter()
+            .any(|s| s.starts_with(r#"DELETE FROM "sessions""#)));
+    }
+
+    #[tokio::test]
+    async fn revoking_the_current_session_clears_the_cookies() {
+        let db = auth_db("user")
+            .append_query_results([[session_row("session-1", "user-1")]])
+            .append_exec_results([exec_result(1)]);
+
+        let response = send_raw(
+            authorized("DELETE", "/me/sessions/session-1")
+                .body(Body::empty())
+                .unwrap(),
+            test_state(db),
+        )
+        .await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let cookies = set_cookies(&response);
+        assert!(!cookies.is_empty());
+        assert!(
+            cookies.iter().all(|c| c.contains("Max-Age=0")),
+            "{cookies:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn revoking_a_session_of_another_user_is_not_found() {
+        // user_id で絞り込んだ検索に一致しないため、削除しない
+        let db = auth_db("user").append_query_results([Vec::<sessions::Model>::new()]);
+        let state = test_state(db);
+        let db = state.db.clone();
+
+        let status = send_with_state(
+            authorized("DELETE", "/me/sessions/session-of-user-2")
+                .body(Body::empty())
+                .unwrap(),
+            state,
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let sql = executed_sql(db);
+        assert!(
+            sql.iter()
+                .any(|s| s.contains(r#""sessions"."user_id" = $"#)),
+            "{sql:#?}"
+        );
+        assert!(!sql.iter().any(|s| s.starts_with("DELETE")));
+    }
+
+    #[tokio::test]
+    async fn revoking_other_sessions_keeps_the_current_one() {
+        let db = auth_db("user")
+            .append_query_results([[
+                session_row("session-2", "user-1"),
+                session_row("session-3", "user-1"),
+            ]])
+            .append_exec_results([exec_result(2)]);
+        let mut state = test_state(db);
+        with_cached_sessions(&mut state, &["session-2-token", "session-3-token"]);
+        let cache = state.session_cache.clone();
+        let db = state.db.clone();
+
+        let response = send_raw(
+            authorized("POST", "/me/sessions/revoke-others")
+                .body(Body::empty())
+                .unwrap(),
+            state,
+        )
+        .await;
+        assert!(set_cookies(&response).is_empty());
+        let (status, body) = json_response(response).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, json!({ "success": true, "revoked": 2 }));
+        for token in ["session-2-token", "session-3-token"] {
+            assert!(cache.get(&SessionCache::key(token)).is_none(), "{token}");
+        }
+        let sql = executed_sql(db);
+        let delete = sql
+            .iter()
+            .find(|s| s.starts_with(r#"DELETE FROM "sessions""#))
+            .unwrap();
+        assert!(
+            delete.contains(r#""sessions"."user_id" = $1 AND "sessions"."id" <> $2"#),
+            "{delete}"
+        );
+    }
+
+    fn profile_update(if_match: Option<&str>) -> Request<Body> {
+        let mut request =
+            authorized("PATCH", "/me").header(header::CONTENT_TYPE, "application/json");
+        if let Some(if_match) = if_match {
+            request = request.header(header::IF_MATCH, if_match);
+        }
+        request
+            .body(Body::from(json!({ "name": "Renamed" }).to_string()))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn profile_update_requires_if_match() {
+        // ハンドラのクエリ結果は用意しない（更新の前に拒否される）
+        let (status, body) = send_json(profile_update(None), test_state(auth_db("user"))).await;
+
+        assert_eq!(status, StatusCode::PRECONDITION_REQUIRED);
+        assert_eq!(body["error"]["code"], "PRECONDITION_REQUIRED");
+    }
+
+    #[tokio::test]
+    async fn profile_update_with_matching_etag_returns_the_new_etag() {
+        let etag = r#""1700000000123456""#;
+        let updated = users::Model {
+            name: "Renamed".into(),
+            updated_at: DateTime::from_timestamp_micros(1_800_000_000_000_000)
+                .unwrap()
+                .fixed_offset(),
+            ..user_row("user-1")
+        };
+        let db = auth_db("user")
+            .append_exec_results([exec_result(1)])
+            .append_query_results([[updated]]);
+        let state = test_state(db);
+        let db = state.db.clone();
+
+        let response = send_raw(profile_update(Some(etag)), state).await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::ETAG], r#""1800000000000000""#);
+        let (_, body) = json_response(response).await;
+        assert_eq!(body["name"], "Renamed");
+
+        // If-Match の ETag の updated_at と一致する場合のみ更新する
+        let statements = executed_statements(db);
+        let update = statements
+            .iter()
+            .find(|s| s.sql.starts_with(r#"UPDATE "users""#))
+            .unwrap();
+        assert!(
+            update.sql.contains(r#""users"."updated_at" IN ($"#),
+            "{}",
+            update.sql
+        );
+        let expected = DateTime::from_timestamp_micros(1_700_000_000_123_456).unwrap();
+        assert!(update
+            .values
+            .as_ref()
+            .unwrap()
+            .0
+            .contains(&sea_orm::Value::from(expected)));
+    }
+
+    #[tokio::test]
+    async fn profile_update_with_stale_etag_is_rejected() {
+        // 弱い ETag は強い比較で一致しないため、更新対象がなく 412
+        for if_match in [r#""1700000000123456""#, r#"W/"1700000000123456""#] {
+            let db = auth_db("user")
+                .append_exec_results([exec_result(0)])
+                .append_query_results([[user_row("user-1")]]);
+
+            let (status, body) = send_json(profile_update(Some(if_match)), test_state(db)).await;
+
+            assert_eq!(status, StatusCode::PRECONDITION_FAILED, "{if_match}");
+            assert_eq!(body["error"]["code"], "PRECONDITION_FAILED");
+        }
+    }
+
+    #[tokio::test]
+    async fn profile_update_with_wildcard_ignores_updated_at() {
+        let db = auth_db("user")
+            .append_exec_results([exec_result(1)])
+            .append_query_results([[user_row("user-1")]]);
+        let state = test_state(db);
+        let db = state.db.clone();
+
+        let status = send_with_state(profile_update(Some("*")), state).await;
+
+        assert_eq!(status, StatusCode::OK);
+        let sql = executed_sql(db);
+        let update = sql
+            .iter()
+            .find(|s| s.starts_with(r#"UPDATE "users""#))
+            .unwrap();
+        assert!(!update.contains(r#""users"."updated_at" IN"#), "{update}");
+    }
+
+    #[tokio::test]
+    async fn profile_update_of_a_withdrawn_user_is_unauthorized() {
+        // 更新対象がなく、ユーザーも見つからない場合は 412 ではなく退会済み
+        let db = auth_db("user")
+            .append_exec_results([exec_result(0)])
+            .append_query_results([Vec::<users::Model>::new()]);
+
+        let (status, body) = send_json(
+            profile_update(Some(r#""1700000000123456""#)),
+            test_state(db),
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body["error"]["code"], "USER_WITHDRAWN");
+    }
+
+    /// アカウントの解除（users の行ロックと accounts の読み込みの結果を用意する）
+    async fn unlink(
+        provider: &str,
+        accounts: Vec<accounts::Model>,
+        db: MockDatabase,
+    ) -> (StatusCode, serde_json::Value, Vec<String>) {
+        let db = db
+            .append_query_results([[user_row("user-1")]])
+            .append_query_results([accounts]);
+        let state = test_state(db);
+        let db = state.db.clone();
+
+        let (status, body) = send_json(
+            authorized("DELETE", &format!("/me/accounts/{provider}"))
+                .body(Body::empty())
+                .unwrap(),
+            state,
+        )
+        .await;
+        (status, body, executed_sql(db))
+    }
+
+    #[tokio::test]
+    async fn unlinking_the_last_account_is_refused() {
+        // 同じプロバイダーのアカウントしかない場合も、解除するとログインできなくなる
+        for accounts in [
+            vec![account_row("user-1", "google")],
+            vec![
+                account_row("user-1", "google"),
+                account_row("user-1", "google"),
+            ],
+        ] {
+            let (status, body, sql) = unlink("google", accounts, auth_db("user")).await;
+
+            assert_eq!(status, StatusCode::BAD_REQUEST);
+            assert_eq!(body["error"]["code"], "FAILED_TO_UNLINK_LAST_ACCOUNT");
+            assert!(!sql.iter().any(|s| s.starts_with("DELETE")), "{sql:#?}");
+            assert!(!sql.iter().any(|s| s == "COMMIT"), "{sql:#?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn unlinking_locks_the_user_row() {
+        let (_, _, sql) = unlink(
+            "google",
+            vec![account_row("user-1", "google")],
+            auth_db("user"),
+        )
+        .await;
+
+        // 同時に別のアカウントを解除しても、最後の 1 つは残るようにする
+        let select = sql
+            .iter()
+            .find(|s| s.starts_with(r#"SELECT "users""#) && s.contains("FOR UPDATE"));
+        assert!(select.is_some(), "{sql:#?}");
+    }
+
+    #[tokio::test]
+    async fn unlinking_an_unknown_provider_is_not_found() {
+        let (status, _, sql) = unlink(
+            "github",
+            vec![account_row("user-1", "google")],
+            auth_db("user"),
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert!(!sql.iter().any(|s| s.starts_with("DELETE")));
+    }
+
+    #[tokio::test]
+    async fn unlinking_keeps_the_other_accounts() {
+        let (status, body, sql) = unlink(
+            "google",
+            vec![
+                account_row("user-1", "credential"),
+                account_row("user-1", "google"),
+            ],
+            auth_db("user").append_exec_results([exec_result(1)]),
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, json!({ "success": true }));
+        let delete = sql
+            .iter()
+            .find(|s| s.starts_with(r#"DELETE FROM "accounts""#))
+            .unwrap();
+        assert!(
+            delete.contains(r#""accounts"."user_id" = $1 AND "accounts"."provider_id" = $2"#),
+            "{delete}"
+        );
+        assert!(sql.iter().any(|s| s == "COMMIT"));
+    }
+
+    /// 猶予期間内（1 日前）に退会したユーザー
+    fn withdrawn_user(id: &str, withdrawn_days_ago: i64) -> users::Model {
+        users::Model {
+            email: format!("deleted_{id}@deleted.local"),
+            deleted_at: Some((Utc::now() - Duration::days(withdrawn_days_ago)).fixed_offset()),
+            ..user_row(id)
+        }
+    }
+
+    fn withdrawal_backup(user_id: &str, accounts: Vec<accounts::Model>) -> user_withdrawals::Model {
+        user_withdrawals::Model {
+            user_id: user_id.into(),
+            email_hash: "hash".into(),
+            encrypted_data: withdrawal::encrypted_backup(
+                SECRET,
+                &format!("{user_id}@example.com"),
+                accounts,
+            ),
+            created_at: Utc::now().fixed_offset(),
+        }
+    }
+
+    fn restore_token(user_id: &str) -> verifications::Model {
+        let now = Utc::now().fixed_offset();
+        verifications::Model {
+            id: "verification-1".into(),
+            identifier: "restore-account:token".into(),
+            value: user_id.into(),
+            expires_at: now + Duration::hours(1),
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    #[tokio::test]
+    async fn restore_confirmation_restores_the_user_and_accounts() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([[restore_token("user-2")]])
+            .append_query_results([[withdrawn_user("user-2", 1)]])
+            .append_query_results([[withdrawal_backup(
+                "user-2",
+                vec![account_row("user-2", "credential")],
+            )]])
+            // 同じメールアドレスの別のユーザーはいない
+            .append_query_results([Vec::<users::Model>::new()])
+            .append_exec_results([
+                exec_result(1),
+                exec_result(1),
+                exec_result(1),
+                exec_result(1),
+            ]);
+        let state = test_state(db);
+        let db = state.db.clone();
+
+        let (status, body) = send_json(
+            Request::builder()
+                .method("POST")
+                .uri("/account/restore/confirm")
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(json!({ "token": "token" }).to_string()))
+                .unwrap(),
+            state,
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::OK, "{body}");
+        let sql = executed_sql(db);
+        let position = |prefix: &str| {
+            sql.iter()
+                .position(|s| s.starts_with(prefix))
+                .unwrap_or_else(|| panic!("{prefix} was not executed: {sql:#?}"))
+        };
+        assert!(sql[position(r#"SELECT "verifications""#)]
+            .contains(r#""verifications"."expires_at" > $"#));
+        assert!(sql[position(r#"SELECT "users""#)].contains("FOR UPDATE"));
+        let restore = position(r#"UPDATE "users""#);
+        assert!(sql[restore].contains(r#""deleted_at" = $"#));
+        let accounts = position(r#"INSERT INTO "accounts""#);
+        let backup = position(r#"DELETE FROM "user_withdrawals""#);
+        let tokens = position(r#"DELETE FROM "verifications""#);
+        let commit = position("COMMIT");
+        assert!(restore < accounts && accounts < backup && backup < tokens && tokens < commit);
+    }
+
+    #[tokio::test]
+    async fn restore_confirmation_rejects_unknown_tokens() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([Vec::<verifications::Model>::new()]);
+
+        let (status, body) = send_json(
+            Request::builder()
+                .method("POST")
+                .uri("/account/restore/confirm")
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(json!({ "token": "unknown" }).to_string()))
+                .unwrap(),
+            test_state(db),
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"]["code"], "INVALID_RESTORE_TOKEN");
+    }
+
+    #[tokio::test]
+    async fn admin_restore_refuses_users_past_the_grace_period() {
+        // AuthConfig::for_tests の猶予期間は 30 日
+        let db = auth_db("admin").append_query_results([[withdrawn_user("user-2", 31)]]);
+        let state = test_state(db);
+        let db = state.db.clone();
+
+        let status = send_with_state(
+            authorized("POST", "/admin/users/user-2/restore")
+                .body(Body::empty())
+                .unwrap(),
+            state,
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let sql = executed_sql(db);
+        assert!(!sql.iter().any(|s| s.starts_with("UPDATE")), "{sql:#?}");
+    }
+
+    #[tokio::test]
+    async fn admin_restore_refuses_when_the_email_is_taken() {
+        // 退会後に同じメールアドレスで別のユーザーが登録している
+        let db = auth_db("admin")
+            .append_query_results([[withdrawn_user("user-2", 1)]])
+            .append_query_results([[withdrawal_backup("user-2", Vec::new())]])
+            .append_query_results([[user_row("user-3")]]);
+        let state = test_state(db);
+        let db = state.db.clone();
+
+        let (status, body) = send_json(
+            authorized("POST", "/admin/users/user-2/restore")
+                .body(Body::empty())
+                .unwrap(),
+            state,
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(body["error"]["code"], "EMAIL_IN_USE");
+        let sql = executed_sql(db);
+        assert!(sql.iter().any(|s| s == "ROLLBACK"), "{sql:#?}");
+        assert!(!sql.iter().any(|s| s.starts_with("UPDATE")), "{sql:#?}");
+    }
+
+    fn restore_request(email: &str, peer: &str, forwarded_for: Option<&str>) -> Request<Body> {
+        let mut request = Request::builder()
+            .method("POST")
+            .uri("/account/restore")
+            .header(header::CONTENT_TYPE, "application/json");
+        if let Some(forwarded_for) = forwarded_for {
+            request = request.header("x-forwarded-for", forwarded_for);
+        }
+        let mut request = request
+            .body(Body::from(json!({ "email": email }).to_string()))
+            .unwrap();
+        request
+            .extensions_mut()
+            .insert(axum::extract::ConnectInfo::<std::net::SocketAddr>(
+                peer.parse().unwrap(),
+            ));
+        request
+    }
+
+    #[tokio::test]
+    async fn restore_requests_are_rate_limited_by_email() {
+        // 検索・送信はバックグラウンドで行うため、クエリ結果は用意しない（失敗してもレスポンスは同じ）
+        let state = test_state(MockDatabase::new(DatabaseBackend::Postgres));
+
+        for i in 0..3 {
+            // 大文字・小文字や前後の空白が違っても同じメールアドレスとして数える
+            let email = ["user@example.com", " User@Example.com", "USER@example.com "][i];
+            let status = send_with_state(
+                restore_request(email, &format!("192.0.2.{i}:1234"), None),
+                state.clone(),
+            )
+            .await;
+            assert_eq!(status, StatusCode::OK, "{email}");
+        }
+
+        let (status, body) = send_json(
+            restore_request("user@example.com", "192.0.2.100:1234", None),
+            state,
+        )
+        .await;
+        assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(body["error"]["code"], "TOO_MANY_REQUESTS");
+    }
+
+    #[tokio::test]
+    async fn restore_requests_are_rate_limited_by_peer_address() {
+        let state = test_state(MockDatabase::new(DatabaseBackend::Postgres));
+
+        // TRUSTED_PROXY_HOPS=0 では X-Forwarded-For を変えても同じ接続元として数える
+        for i in 0..10 {
+            let status = send_with_state(
+                restore_request(
+                    &format!("user{i}@example.com"),
+                    "192.0.2.1:1234",
+                    Some(&format!("203.0.113.{i}")),
+                ),
+                state.clone(),
+            )
+            .await;
+            assert_eq!(status, StatusCode::OK);
+        }
+
+        let status = send_with_state(
+            restore_request("other@example.com", "192.0.2.1:5678", Some("203.0.113.200")),
+            state.clone(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+
+        let status = send_with_state(
+            restore_request("other@example.com", "192.0.2.2:1234", None),
+            state,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    /// created_at が base から minutes 分前のユーザー
+    fn user_created_minutes_ago(id: &str, minutes: i64) -> users::Model {
+        let base = DateTime::parse_from_rfc3339("2024-06-01T12:00:00.123456Z").unwrap();
+        users::Model {
+            created_at: base - Duration::minutes(minutes),
+            ..user_row(id)
+        }
+    }
+
+    async fn list_users(
+        uri: &str,
+        db: MockDatabase,
+    ) -> (StatusCode, serde_json::Value, Vec<Statement>) {
+        let state = test_state(db);
+        let db = state.db.clone();
+        let (status, body) =
+            send_json(authorized("GET", uri).body(Body::empty()).unwrap(), state).await;
+        (status, body, executed_statements(db))
+    }
+
+    fn user_ids(body: &serde_json::Value) -> Vec<&str> {
+        body["users"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|user| user["id"].as_str().unwrap())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn user_list_pages_with_a_cursor() {
+        // 1 ページ目: 次のページの有無を判定するため limit + 1 件を読む
+        let db = auth_db("admin")
+            .append_query_results([[
+                user_created_minutes_ago("user-c", 0),
+                user_created_minutes_ago("user-b", 1),
+                user_created_minutes_ago("user-a", 2),
+            ]])
+            .append_query_results([[account_row("user-c", "credential")]]);
+
+        let (status, body, statements) = list_users("/admin/users?limit=2", db).await;
+
+        assert_eq!(status, StatusCode::OK, "{body}");
+        assert_eq!(user_ids(&body), ["user-c", "user-b"]);
+        assert_eq!(body["users"][0]["providers"], json!(["credential"]));
+        let select = &statements[2];
+        assert!(
+            select
+                .sql
+                .ends_with(r#"ORDER BY "users"."created_at" DESC, "users"."id" DESC LIMIT $1"#),
+            "{}",
+            select.sql
+        );
+        assert_eq!(
+            select.values.as_ref().unwrap().0,
+            [sea_orm::Value::BigUnsigned(Some(3))]
+        );
+
+        // 2 ページ目: 最後に返したユーザー（user-b）より後ろから読む
+        let cursor = body["next_cursor"].as_str().unwrap();
+        let db = auth_db("admin")
+            .append_query_results([[user_created_minutes_ago("user-a", 2)]])
+            .append_query_results([Vec::<accounts::Model>::new()]);
+
+        let (status, body, statements) =
+            list_users(&format!("/admin/users?limit=2&cursor={cursor}"), db).await;
+
+        assert_eq!(status, StatusCode::OK, "{body}");
+        assert_eq!(user_ids(&body), ["user-a"]);
+        assert_eq!(body["next_cursor"], serde_json::Value::Null);
+        let select = &statements[2];
+        assert!(
+            select.sql.contains(
+                r#"WHERE "users"."created_at" < $1 OR ("users"."created_at" = $2 AND "users"."id" < $3)"#
+            ),
+            "{}",
+            select.sql
+        );
+        let created_at = user_created_minutes_ago("user-b", 1).created_at;
+        assert_eq!(
+            select.values.as_ref().unwrap().0[..3],
+            [
+                sea_orm::Value::from(created_at),
+                sea_orm::Value::from(created_at),
+                sea_orm::Value::from("user-b"),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn user_list_cursor_follows_ascending_order() {
+        let db = auth_db("admin")
+            .append_query_results([[user_row("user-a"), user_row("user-b")]])
+            .append_query_results([Vec::<accounts::Model>::new()]);
+        let (_, body, _) = list_users("/admin/users?sort=name&order=asc&limit=1", db).await;
+        let cursor = body["next_cursor"].as_str().unwrap();
+
+        let db = auth_db("admin")
+            .append_query_results([[user_row("user-b")]])
+            .append_query_results([Vec::<accounts::Model>::new()]);
+        let (status, _, statements) = list_users(
+            &format!("/admin/users?sort=name&order=asc&limit=1&cursor={cursor}"),
+            db,
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert!(
+            statements[2].sql.contains(
+                r#"WHERE "users"."name" > $1 OR ("users"."name" = $2 AND "users"."id" > $3)"#
+            ),
+            "{}",
+            statements[2].sql
+        );
+    }
+
+    #[tokio::test]
+    async fn user_list_rejects_invalid_cursors_and_limits() {
+        let db = auth_db("admin")
+            .append_query_results([[user_row("user-a"), user_row("user-b")]])
+            .append_query_results([Vec::<accounts::Model>::new()]);
+        let (_, body, _) = list_users("/admin/users?limit=1", db).await;
+        let cursor = body["next_cursor"].as_str().unwrap().to_string();
+
+        for uri in [
+            // 並び順が変わるとカーソルの位置の意味が変わる
+            format!("/admin/users?limit=1&sort=name&cursor={cursor}"),
+            format!("/admin/users?limit=1&order=asc&cursor={cursor}"),
+            "/admin/users?cursor=not-a-cursor".to_string(),
+            "/admin/users?limit=0".to_string(),
+            "/admin/users?limit=101".to_string(),
+        ] {
+            // ハンドラのクエリ結果は用意しない（検索の前に拒否される）
+            let (status, body, _) = list_users(&uri, auth_db("admin")).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{uri}");
+            assert_eq!(body["error"]["code"], "VALIDATION_ERROR", "{uri}");
+        }
+    }
+
+    /// 集計クエリ（into_tuple）の 1 行
+    /// MockDatabase は列番号で読むため、キーを列の順に並ぶ番号にする
+    fn tuple_row<const N: usize>(
+        values: [sea_orm::Value; N],
+    ) -> std::collections::BTreeMap<String, sea_orm::Value> {
+        values
+            .into_iter()
+            .enumerate()
+            .map(|(i, value)| (i.to_string(), value))
+            .collect()
+    }
+
+    fn date(s: &str) -> chrono::NaiveDate {
+        s.parse().unwrap()
+    }
+
+    #[tokio::test]
+    async fn stats_counts_signups_and_withdrawals_from_user_events() {
+        // 集計は登録数・メール認証状況・退会数・セッション数・ログイン方法別の順に実行される
+        let db = auth_db("admin")
+            .append_query_results([vec![
+                tuple_row([date("2024-06-03").into(), 2i64.into()]),
+                tuple_row([date("2024-06-05").into(), 1i64.into()]),
+            ]])
+            .append_query_results([vec![
+                tuple_row([true.into(), 3i64.into()]),
+                tuple_row([false.into(), 1i64.into()]),
+            ]])
+            .append_query_results([vec![tuple_row([date("2024-06-03").into(), 4i64.into()])]])
+            .append_query_results([vec![tuple_row([5i64.into(), 2i64.into()])]])
+            .append_query_results([vec![tuple_row(["credential".into(), 5i64.into()])]]);
+        let state = test_state(db);
+        let db = state.db.clone();
+
+        let (status, body) = send_json(
+            authorized("GET", "/admin/stats?from=2024-06-03&to=2024-06-05")
+                .body(Body::empty())
+                .unwrap(),
+            state,
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::OK, "{body}");
+        assert_eq!(body["source"], "live");
+        assert_eq!(
+            body["signups_per_day"],
+            json!([
+                { "date": "2024-06-03", "count": 2 },
+                { "date": "2024-06-04", "count": 0 },
+                { "date": "2024-06-05", "count": 1 },
+            ])
+        );
+        assert_eq!(
+            body["email_verification"],
+            json!({ "verified": 3, "unverified": 1, "verified_ratio": 0.75 })
+        );
+        assert_eq!(
+            body["withdrawals_per_week"],
+            json!([{ "week_start": "2024-06-03", "count": 4 }])
+        );
+        assert_eq!(
+            body["active_sessions"],
+            json!({ "sessions": 5, "users": 2 })
+        );
+        assert_eq!(
+            body["sessions_per_provider"],
+            json!([{ "provider": "credential", "sessions": 5 }])
+        );
+
+        // 登録数・退会数は users ではなく user_events を種類で絞り込んで数える
+        // （完全削除されたユーザーも含めるため）
+        let statements = executed_statements(db);
+        let start = Utc.with_ymd_and_hms(2024, 6, 3, 0, 0, 0).unwrap();
+        let end = Utc.with_ymd_and_hms(2024, 6, 6, 0, 0, 0).unwrap();
+        for (statement, kind) in [(&statements[2], "signup"), (&statements[4], "withdrawal")] {
+            assert!(
+                statement
+                    .sql
+                    .contains(r#"FROM "user_events" WHERE "user_events"."kind" = $1"#),
+                "{}",
+                statement.sql
+            );
+            assert_eq!(
+                statement.values.as_ref().unwrap().0,
+                [
+                    sea_orm::Value::from(kind),
+                    sea_orm::Value::from(start),
+                    sea_orm::Value::from(end),
+                ]
+            );
+        }
+        assert!(
+            statements[3].sql.contains(r#"FROM "users""#),
+            "{}",
+            statements[3].sql
+        );
+    }
 
-    Router::new().merge(public).merge(protected)
+    #[tokio::test]
+    async fn stats_rejects_invalid_ranges() {
+        for uri in [
+            "/admin/stats?from=2024-06-05&to=2024-06-03",
+            "/admin/stats?from=2023-01-01&to=2024-01-02",
+        ] {
+            let (status, body) = send_json(
+                authorized("GET", uri).body(Body::empty()).unwrap(),
+                test_state(auth_db("admin")),
+            )
+            .await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{uri}");
+            assert_eq!(body["error"]["code"], "VALIDATION_ERROR", "{uri}");
+        }
+    }
 }
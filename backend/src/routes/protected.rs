@@ -1,7 +1,32 @@
-use axum::{extract::Extension, routing::get, Json, Router};
-use serde::Serialize;
+use axum::{
+    extract::{
+        multipart::MultipartError, DefaultBodyLimit, Extension, Multipart, Path, Query, State,
+    },
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{delete, get, post},
+    Json, Router,
+};
+use axum_extra::extract::CookieJar;
+use chrono::{DateTime, FixedOffset, SubsecRound, Utc};
+use sea_orm::{
+    sea_query::Expr, ColumnTrait, ConnectionTrait, EntityTrait, ModelTrait, QueryFilter,
+    QueryOrder, QuerySelect, TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+use url::Url;
 
-use crate::middleware::AuthExtension;
+use crate::authz::Permission;
+use crate::avatar;
+use crate::device::DeviceInfo;
+use crate::entity::{accounts, sessions, users};
+use crate::error::ApiError;
+use crate::export::{ExportFile, ExportFormat, ExportJob, ExportStatus};
+use crate::geoip::{self, GeoLocation};
+use crate::middleware::{clear_session_cookies, AuthError, AuthExtension};
+use crate::password::ConfirmedPassword;
+use crate::session_cache::SessionCache;
+use crate::withdrawal;
 use crate::AppState;
 
 #[derive(Serialize)]
@@ -11,20 +36,845 @@ struct MeResponse {
     email: String,
     email_verified: bool,
     image: Option<String>,
+    roles: Vec<String>,
+    /// 現在のセッションの端末情報
+    device: DeviceInfo,
+    /// なりすまし中の場合、なりすましている管理者のユーザー ID
+    impersonated_by: Option<String>,
 }
 
-/// 認証済みユーザー情報を返す
-async fn me(Extension(auth): Extension<AuthExtension>) -> Json<MeResponse> {
+/// 認証済みユーザー情報を返す（ETag 付き）
+async fn me(Extension(auth): Extension<AuthExtension>) -> impl IntoResponse {
     let user = auth.0;
-    Json(MeResponse {
-        id: user.id,
-        name: user.name,
-        email: user.email,
-        email_verified: user.email_verified,
-        image: user.image,
-    })
+    (
+        [(header::ETAG, etag(&user.updated_at))],
+        Json(MeResponse {
+            id: user.id,
+            name: user.name,
+            email: user.email,
+            email_verified: user.email_verified,
+            image: user.image,
+            roles: user.roles,
+            device: user.device,
+            impersonated_by: user.impersonated_by,
+        }),
+    )
 }
 
+// ============================================================
+// プロフィール更新 API
+// - users.updated_at から生成した ETag を If-Match で送ることで、
+//   別のタブなどで先に更新されていた場合は 412 で上書きを防ぐ
+// ============================================================
+
+const NAME_MAX_LENGTH: usize = 100;
+const IMAGE_URL_MAX_LENGTH: usize = 2048;
+
+#[derive(Deserialize)]
+struct UpdateProfileRequest {
+    name: Option<String>,
+    /// 未指定は変更なし、null は画像の削除
+    #[serde(default, deserialize_with = "nullable")]
+    image: Option<Option<String>>,
+}
+
+/// 「フィールドなし」と「null」を区別する
+fn nullable<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+/// users.updated_at（マイクロ秒）から ETag を生成
+fn etag(updated_at: &DateTime<FixedOffset>) -> String {
+    format!("\"{}\"", updated_at.timestamp_micros())
+}
+
+/// If-Match の条件
+enum IfMatch {
+    /// "*"（現在の状態に関係なく更新）
+    Any,
+    /// いずれかの ETag の updated_at と一致する場合のみ更新
+    UpdatedAt(Vec<DateTime<Utc>>),
+}
+
+fn parse_if_match(headers: &HeaderMap) -> Result<IfMatch, ApiError> {
+    let value = headers
+        .get(header::IF_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .ok_or_else(|| {
+            ApiError::new(
+                StatusCode::PRECONDITION_REQUIRED,
+                "PRECONDITION_REQUIRED",
+                "If-Match header is required",
+            )
+        })?;
+
+    if value.trim() == "*" {
+        return Ok(IfMatch::Any);
+    }
+
+    // If-Match は強い比較のため、弱い ETag（W/"..."）は一致しない
+    let updated_at = value
+        .split(',')
+        .filter_map(|tag| {
+            tag.trim()
+                .strip_prefix('"')?
+                .strip_suffix('"')?
+                .parse()
+                .ok()
+        })
+        .filter_map(DateTime::from_timestamp_micros)
+        .collect();
+    Ok(IfMatch::UpdatedAt(updated_at))
+}
+
+/// 入力値を検証し、更新する値を返す
+fn validate_profile(
+    body: UpdateProfileRequest,
+) -> Result<(Option<String>, Option<Option<String>>), ApiError> {
+    let name = body
+        .name
+        .map(|name| {
+            let name = name.trim().to_string();
+            match name.chars().count() {
+                0 => Err(ApiError::validation("name must not be empty")),
+                n if n > NAME_MAX_LENGTH => Err(ApiError::validation(format!(
+                    "name must be at most {NAME_MAX_LENGTH} characters"
+                ))),
+                _ => Ok(name),
+            }
+        })
+        .transpose()?;
+
+    let image = body
+        .image
+        .map(|image| image.map(validate_image_url).transpose())
+        .transpose()?;
+
+    if name.is_none() && image.is_none() {
+        return Err(ApiError::validation("No fields to update"));
+    }
+    Ok((name, image))
+}
+
+/// 画像 URL は http / https のみ許可
+fn validate_image_url(image: String) -> Result<String, ApiError> {
+    if image.len() > IMAGE_URL_MAX_LENGTH {
+        return Err(ApiError::validation(format!(
+            "image must be at most {IMAGE_URL_MAX_LENGTH} characters"
+        )));
+    }
+    let url = Url::parse(&image).map_err(|_| ApiError::validation("image must be a valid URL"))?;
+    if !matches!(url.scheme(), "http" | "https") || url.host_str().is_none() {
+        return Err(ApiError::validation("image must be an http or https URL"));
+    }
+    Ok(image)
+}
+
+/// プロフィール（name / image）を更新
+async fn update_me(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthExtension>,
+    headers: HeaderMap,
+    Json(body): Json<UpdateProfileRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let user = auth.0;
+    user.ensure_not_impersonated()?;
+    let if_match = parse_if_match(&headers)?;
+    let (name, image) = validate_profile(body)?;
+
+    // DB の精度（マイクロ秒）に揃えておき、返す ETag と保存値を一致させる
+    let now = Utc::now().trunc_subsecs(6);
+
+    let mut update = users::Entity::update_many()
+        .col_expr(users::Column::UpdatedAt, Expr::value(now))
+        .filter(users::Column::Id.eq(&user.id))
+        .filter(users::Column::DeletedAt.is_null());
+    if let Some(name) = name {
+        update = update.col_expr(users::Column::Name, Expr::value(name));
+    }
+    if let Some(image) = image {
+        update = update.col_expr(users::Column::Image, Expr::value(image));
+    }
+    if let IfMatch::UpdatedAt(updated_at) = if_match {
+        update = update.filter(users::Column::UpdatedAt.is_in(updated_at));
+    }
+
+    let result = update.exec(state.db.as_ref()).await?;
+    if result.rows_affected == 0 {
+        let exists = users::Entity::find_by_id(&user.id)
+            .filter(users::Column::DeletedAt.is_null())
+            .one(state.db.as_ref())
+            .await?
+            .is_some();
+        if !exists {
+            return Err(AuthError::UserWithdrawn.into());
+        }
+        return Err(ApiError::new(
+            StatusCode::PRECONDITION_FAILED,
+            "PRECONDITION_FAILED",
+            "Profile has been modified by another request",
+        ));
+    }
+
+    // 他インスタンスには NOTIFY で伝わるが、自インスタンスは即時に破棄
+    state.session_cache.invalidate_user(&user.id);
+
+    let updated = users::Entity::find_by_id(&user.id)
+        .one(state.db.as_ref())
+        .await?
+        .ok_or(AuthError::UserWithdrawn)?;
+
+    Ok((
+        [(header::ETAG, etag(&updated.updated_at))],
+        Json(MeResponse {
+            id: updated.id,
+            name: updated.name,
+            email: updated.email,
+            email_verified: updated.email_verified,
+            image: updated.image,
+            roles: user.roles,
+            device: user.device,
+            impersonated_by: user.impersonated_by,
+        }),
+    ))
+}
+
+#[derive(Deserialize)]
+struct PermissionCheckRequest {
+    permissions: Permission,
+}
+
+#[derive(Serialize)]
+struct PermissionCheckResponse {
+    success: bool,
+}
+
+/// 現在のユーザーが指定した権限を持っているかを返す
+/// （Better Auth の hasPermission と同じ判定）
+async fn check_permissions(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthExtension>,
+    Json(body): Json<PermissionCheckRequest>,
+) -> Result<Json<PermissionCheckResponse>, ApiError> {
+    if body.permissions.is_empty() {
+        return Err(ApiError::validation("No permissions were passed"));
+    }
+
+    let success = state
+        .access_control
+        .has_permission(&auth.0, &body.permissions);
+    Ok(Json(PermissionCheckResponse { success }))
+}
+
+// ============================================================
+// 退会 API
+// ============================================================
+
+#[derive(Serialize)]
+struct WithdrawResponse {
+    success: bool,
+    message: &'static str,
+}
+
+/// 退会処理
+///
+/// 1. メール/パスワードユーザーの場合、パスワードを検証（ConfirmedPassword）
+/// 2. 以下を1つのトランザクションで実行
+///    - 元のユーザー情報・アカウントを暗号化して保存（猶予期間内は復元可能）
+///    - users を匿名化し deleted_at を設定（ソフトデリート）
+///    - sessions を全削除
+///    - accounts を全削除
+/// 3. Cookie を削除
+async fn withdraw(
+    State(state): State<AppState>,
+    ConfirmedPassword(user, _): ConfirmedPassword,
+) -> Result<(CookieJar, Json<WithdrawResponse>), ApiError> {
+    user.ensure_not_impersonated()?;
+
+    let now = Utc::now();
+    let txn = state.db.begin().await?;
+
+    // 復元用に元のデータを暗号化して保存
+    withdrawal::save_backup(&txn, &state.auth_config, &user.id).await?;
+
+    // ソフトデリート: メール・名前を匿名化し、deleted_at を設定
+    let result = users::Entity::update_many()
+        .col_expr(
+            users::Column::Email,
+            Expr::value(format!("deleted_{}@deleted.local", user.id)),
+        )
+        .col_expr(users::Column::Name, Expr::value("退会済みユーザー"))
+        .col_expr(users::Column::Image, Expr::value(Option::<String>::None))
+        .col_expr(users::Column::DeletedAt, Expr::value(now))
+        .col_expr(users::Column::UpdatedAt, Expr::value(now))
+        .filter(users::Column::Id.eq(&user.id))
+        .filter(users::Column::DeletedAt.is_null())
+        .exec(&txn)
+        .await?;
+
+    if result.rows_affected == 0 {
+        // 同時に退会処理が行われた場合など
+        return Err(AuthError::UserWithdrawn.into());
+    }
+
+    // セッションを削除（全端末からログアウト）
+    sessions::Entity::delete_many()
+        .filter(sessions::Column::UserId.eq(&user.id))
+        .exec(&txn)
+        .await?;
+
+    // アカウント情報を削除（パスワード・OAuth 連携を解除）
+    accounts::Entity::delete_many()
+        .filter(accounts::Column::UserId.eq(&user.id))
+        .exec(&txn)
+        .await?;
+
+    txn.commit().await?;
+
+    // 他インスタンスには NOTIFY で伝わるが、自インスタンスは即時に破棄
+    state.session_cache.invalidate_user(&user.id);
+
+    tracing::info!("User withdrew: {}", user.id);
+
+    Ok((
+        clear_session_cookies(&state.auth_config),
+        Json(WithdrawResponse {
+            success: true,
+            message: "退会処理が完了しました",
+        }),
+    ))
+}
+
+// ============================================================
+// アバター画像 API
+// - multipart/form-data の "file" フィールドで画像を受け取る
+// - サムネイルを BlobStore に保存し、users.image を更新する
+// ============================================================
+
+#[derive(Serialize)]
+struct ThumbnailResponse {
+    size: u32,
+    url: String,
+}
+
+#[derive(Serialize)]
+struct AvatarResponse {
+    image: String,
+    thumbnails: Vec<ThumbnailResponse>,
+}
+
+/// アバター画像をアップロード
+async fn upload_avatar(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthExtension>,
+    mut multipart: Multipart,
+) -> Result<impl IntoResponse, ApiError> {
+    let user = auth.0;
+    user.ensure_not_impersonated()?;
+
+    let multipart_error = |e: MultipartError| match e.status() {
+        StatusCode::PAYLOAD_TOO_LARGE => ApiError::new(
+            StatusCode::PAYLOAD_TOO_LARGE,
+            "PAYLOAD_TOO_LARGE",
+            format!("file must be at most {} bytes", avatar::MAX_UPLOAD_BYTES),
+        ),
+        _ => ApiError::validation(e.body_text()),
+    };
+    let mut data = None;
+    while let Some(field) = multipart.next_field().await.map_err(multipart_error)? {
+        if field.name() == Some("file") {
+            data = Some(field.bytes().await.map_err(multipart_error)?);
+            break;
+        }
+    }
+    let data = data.ok_or_else(|| ApiError::validation("file is required"))?;
+
+    let thumbnails = tokio::task::spawn_blocking(move || avatar::make_thumbnails(&data))
+        .await
+        .map_err(|e| {
+            tracing::error!("Avatar processing task failed: {}", e);
+            ApiError::internal("Internal server error")
+        })??;
+
+    let mut urls = Vec::with_capacity(thumbnails.len());
+    for thumbnail in thumbnails {
+        let key = avatar::key(&user.id, thumbnail.size);
+        let url = state
+            .blob_store
+            .put(&key, thumbnail.data, avatar::CONTENT_TYPE)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to store avatar: {}", e);
+                ApiError::internal("Internal server error")
+            })?;
+        urls.push(ThumbnailResponse {
+            size: thumbnail.size,
+            url,
+        });
+    }
+
+    // 同じ key に上書きするため、ブラウザ・CDN のキャッシュ対策にバージョンを付ける
+    let now = Utc::now().trunc_subsecs(6);
+    let image = format!("{}?v={}", urls[0].url, now.timestamp_millis());
+
+    let result = users::Entity::update_many()
+        .col_expr(users::Column::Image, Expr::value(&image))
+        .col_expr(users::Column::UpdatedAt, Expr::value(now))
+        .filter(users::Column::Id.eq(&user.id))
+        .filter(users::Column::DeletedAt.is_null())
+        .exec(state.db.as_ref())
+        .await?;
+    if result.rows_affected == 0 {
+        return Err(AuthError::UserWithdrawn.into());
+    }
+
+    // 他インスタンスには NOTIFY で伝わるが、自インスタンスは即時に破棄
+    state.session_cache.invalidate_user(&user.id);
+
+    Ok((
+        [(header::ETAG, etag(&now.fixed_offset()))],
+        Json(AvatarResponse {
+            image,
+            thumbnails: urls,
+        }),
+    ))
+}
+
+// ============================================================
+// セッション管理 API（/api/me/sessions）
+// - 自分のセッションのみ操作可能（他人のセッションは 404）
+// - トークンはレスポンスに含めない
+// ============================================================
+
+#[derive(Serialize)]
+struct SessionResponse {
+    id: String,
+    ip_address: Option<String>,
+    user_agent: Option<String>,
+    /// user_agent を解析した端末情報
+    device: DeviceInfo,
+    /// ip_address から推定した位置情報（GeoIP 無効時は null）
+    location: Option<GeoLocation>,
+    /// 過去のログインにない国からのログインかどうか
+    new_country: bool,
+    created_at: DateTime<FixedOffset>,
+    updated_at: DateTime<FixedOffset>,
+    expires_at: DateTime<FixedOffset>,
+    /// このリクエストのセッションかどうか
+    current: bool,
+}
+
+#[derive(Serialize)]
+struct SessionListResponse {
+    sessions: Vec<SessionResponse>,
+}
+
+#[derive(Serialize)]
+struct RevokeResponse {
+    success: bool,
+    /// 削除したセッション数
+    revoked: u64,
+}
+
+/// 有効なセッションの一覧（新しい順）
+async fn list_sessions(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthExtension>,
+) -> Result<Json<SessionListResponse>, ApiError> {
+    let user = auth.0;
+
+    // 期限切れのセッションもログイン履歴として new_country の判定に使うため、古い順に全件取得
+    let history = sessions::Entity::find()
+        .filter(sessions::Column::UserId.eq(&user.id))
+        .order_by_asc(sessions::Column::CreatedAt)
+        .all(state.db.as_ref())
+        .await?;
+
+    let now = Utc::now();
+    let locations: Vec<_> = history
+        .iter()
+        .map(|session| state.geoip.lookup(session.ip_address.as_deref()))
+        .collect();
+    let new_countries = geoip::new_countries(
+        locations
+            .iter()
+            .map(|location| location.as_ref().and_then(|l| l.country_code.as_deref())),
+    );
+    let mut sessions = Vec::new();
+
+    for ((session, location), new_country) in history.into_iter().zip(locations).zip(new_countries)
+    {
+        if session.expires_at <= now {
+            continue;
+        }
+
+        sessions.push(SessionResponse {
+            current: session.id == user.session_id,
+            id: session.id,
+            ip_address: session.ip_address,
+            device: DeviceInfo::parse(session.user_agent.as_deref()),
+            location,
+            new_country,
+            user_agent: session.user_agent,
+            created_at: session.created_at,
+            updated_at: session.updated_at,
+            expires_at: session.expires_at,
+        });
+    }
+
+    sessions.sort_by_key(|s| std::cmp::Reverse(s.updated_at));
+
+    Ok(Json(SessionListResponse { sessions }))
+}
+
+/// 指定したセッションを削除（現在のセッションの場合は Cookie も削除）
+async fn revoke_session(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthExtension>,
+    Path(id): Path<String>,
+) -> Result<(CookieJar, Json<RevokeResponse>), ApiError> {
+    let user = auth.0;
+    user.ensure_not_impersonated()?;
+
+    // 他人のセッションは存在しないものとして扱う
+    let session = sessions::Entity::find_by_id(&id)
+        .filter(sessions::Column::UserId.eq(&user.id))
+        .one(state.db.as_ref())
+        .await?
+        .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, "NOT_FOUND", "Session not found"))?;
+
+    sessions::Entity::delete_by_id(&session.id)
+        .exec(state.db.as_ref())
+        .await?;
+
+    // 他インスタンスには NOTIFY で伝わるが、自インスタンスは即時に破棄
+    state
+        .session_cache
+        .invalidate_session(&SessionCache::key(&session.token));
+
+    let cookies = if session.id == user.session_id {
+        clear_session_cookies(&state.auth_config)
+    } else {
+        CookieJar::new()
+    };
+
+    Ok((
+        cookies,
+        Json(RevokeResponse {
+            success: true,
+            revoked: 1,
+        }),
+    ))
+}
+
+/// 現在のセッション以外をすべて削除
+async fn revoke_other_sessions(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthExtension>,
+) -> Result<Json<RevokeResponse>, ApiError> {
+    let user = auth.0;
+    user.ensure_not_impersonated()?;
+
+    let others = sessions::Entity::find()
+        .filter(sessions::Column::UserId.eq(&user.id))
+        .filter(sessions::Column::Id.ne(&user.session_id))
+        .all(state.db.as_ref())
+        .await?;
+
+    let result = sessions::Entity::delete_many()
+        .filter(sessions::Column::UserId.eq(&user.id))
+        .filter(sessions::Column::Id.ne(&user.session_id))
+        .exec(state.db.as_ref())
+        .await?;
+
+    for session in &others {
+        state
+            .session_cache
+            .invalidate_session(&SessionCache::key(&session.token));
+    }
+
+    Ok(Json(RevokeResponse {
+        success: true,
+        revoked: result.rows_affected,
+    }))
+}
+
+// ============================================================
+// 連携アカウント API（/api/me/accounts）
+// - credential（メール/パスワード）や google などのログイン方法の一覧・解除
+// - トークン・パスワードはレスポンスに含めない
+// ============================================================
+
+#[derive(Serialize)]
+pub(super) struct AccountResponse {
+    /// "credential" / "google" など
+    provider: String,
+    account_id: String,
+    scopes: Vec<String>,
+    created_at: DateTime<FixedOffset>,
+    updated_at: DateTime<FixedOffset>,
+}
+
+impl From<accounts::Model> for AccountResponse {
+    fn from(account: accounts::Model) -> Self {
+        Self {
+            provider: account.provider_id,
+            account_id: account.account_id,
+            // Better Auth は scope をカンマ区切りで保存する
+            scopes: account
+                .scope
+                .as_deref()
+                .unwrap_or_default()
+                .split(',')
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(String::from)
+                .collect(),
+            created_at: account.created_at,
+            updated_at: account.updated_at,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct AccountListResponse {
+    accounts: Vec<AccountResponse>,
+}
+
+#[derive(Serialize)]
+struct UnlinkResponse {
+    success: bool,
+}
+
+/// 退会済みでないユーザーと、users → accounts のリレーションで紐づくアカウント
+async fn find_user_with_accounts<C: ConnectionTrait>(
+    db: &C,
+    user_id: &str,
+    lock: bool,
+) -> Result<(users::Model, Vec<accounts::Model>), ApiError> {
+    let mut query = users::Entity::find_by_id(user_id).filter(users::Column::DeletedAt.is_null());
+    if lock {
+        query = query.lock_exclusive();
+    }
+    let user = query.one(db).await?.ok_or(AuthError::UserWithdrawn)?;
+    let accounts = user.find_related(accounts::Entity).all(db).await?;
+    Ok((user, accounts))
+}
+
+/// 連携アカウントの一覧
+async fn list_accounts(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthExtension>,
+) -> Result<Json<AccountListResponse>, ApiError> {
+    let (_, accounts) = find_user_with_accounts(state.db.as_ref(), &auth.0.id, false).await?;
+
+    let accounts = accounts.into_iter().map(AccountResponse::from).collect();
+
+    Ok(Json(AccountListResponse { accounts }))
+}
+
+/// 連携アカウントの解除（最後のログイン方法は解除できない）
+async fn unlink_account(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthExtension>,
+    Path(provider): Path<String>,
+) -> Result<Json<UnlinkResponse>, ApiError> {
+    auth.0.ensure_not_impersonated()?;
+
+    let txn = state.db.begin().await?;
+
+    // 同時に別のアカウントを解除してログイン方法がなくなるのを防ぐため、users の行をロックする
+    let (user, accounts) = find_user_with_accounts(&txn, &auth.0.id, true).await?;
+
+    if !accounts.iter().any(|a| a.provider_id == provider) {
+        return Err(ApiError::new(
+            StatusCode::NOT_FOUND,
+            "NOT_FOUND",
+            "Account not found",
+        ));
+    }
+    if accounts.iter().all(|a| a.provider_id == provider) {
+        // Better Auth の unlinkAccount と同じエラーコード
+        return Err(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            "FAILED_TO_UNLINK_LAST_ACCOUNT",
+            "You can't unlink your last account",
+        ));
+    }
+
+    accounts::Entity::delete_many()
+        .filter(accounts::Column::UserId.eq(&user.id))
+        .filter(accounts::Column::ProviderId.eq(&provider))
+        .exec(&txn)
+        .await?;
+
+    txn.commit().await?;
+
+    tracing::info!("Account unlinked: user={}, provider={}", user.id, provider);
+
+    Ok(Json(UnlinkResponse { success: true }))
+}
+
+// ============================================================
+// データエクスポート API（/api/me/export）
+// - 件数が少なければそのままファイルを返し、多ければ 202 でジョブを返す
+// ============================================================
+
+#[derive(Deserialize)]
+struct ExportQuery {
+    #[serde(default)]
+    format: ExportFormat,
+}
+
+#[derive(Serialize)]
+struct ExportJobResponse {
+    id: String,
+    /// "pending" / "ready" / "failed"
+    status: &'static str,
+    format: ExportFormat,
+    created_at: DateTime<Utc>,
+    /// status が ready の場合のみ
+    download_url: Option<String>,
+}
+
+impl From<&ExportJob> for ExportJobResponse {
+    fn from(job: &ExportJob) -> Self {
+        let (status, download_url) = match job.status {
+            ExportStatus::Pending => ("pending", None),
+            ExportStatus::Ready => (
+                "ready",
+                Some(format!("/api/me/export/jobs/{}/download", job.id)),
+            ),
+            ExportStatus::Failed => ("failed", None),
+        };
+        Self {
+            id: job.id.clone(),
+            status,
+            format: job.format,
+            created_at: job.created_at,
+            download_url,
+        }
+    }
+}
+
+fn export_file_response(file: ExportFile) -> Response {
+    (
+        [
+            (header::CONTENT_TYPE, file.content_type.to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", file.filename),
+            ),
+            // 個人データのためキャッシュさせない
+            (header::CACHE_CONTROL, "no-store".to_string()),
+        ],
+        file.data,
+    )
+        .into_response()
+}
+
+/// 自分のデータをエクスポート（?format=json|zip）
+async fn export_me(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthExtension>,
+    Query(query): Query<ExportQuery>,
+) -> Result<Response, ApiError> {
+    let user = auth.0;
+    user.ensure_not_impersonated()?;
+
+    if state.exporter.is_large(state.db.as_ref(), &user).await? {
+        let job = state
+            .exporter
+            .start_job(state.db.clone(), user, query.format)
+            .await?;
+        let status_url = format!("/api/me/export/jobs/{}", job.id);
+        return Ok((
+            StatusCode::ACCEPTED,
+            [(header::LOCATION, status_url)],
+            Json(ExportJobResponse::from(&job)),
+        )
+            .into_response());
+    }
+
+    let file = state.exporter.build(state.db.as_ref(), &user, query.format).await?;
+    Ok(export_file_response(file))
+}
+
+fn export_job_not_found() -> ApiError {
+    ApiError::new(StatusCode::NOT_FOUND, "NOT_FOUND", "Export job not found")
+}
+
+/// エクスポートジョブの状態
+async fn export_job_status(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthExtension>,
+    Path(id): Path<String>,
+) -> Result<Json<ExportJobResponse>, ApiError> {
+    let job = state
+        .exporter
+        .job(&id, &auth.0.id)
+        .await?
+        .ok_or_else(export_job_not_found)?;
+    Ok(Json(ExportJobResponse::from(&job)))
+}
+
+/// 生成済みのエクスポートファイルをダウンロード
+async fn download_export(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthExtension>,
+    Path(id): Path<String>,
+) -> Result<Response, ApiError> {
+    let job = state
+        .exporter
+        .job(&id, &auth.0.id)
+        .await?
+        .ok_or_else(export_job_not_found)?;
+
+    if job.status != ExportStatus::Ready {
+        return Err(ApiError::new(
+            StatusCode::CONFLICT,
+            "EXPORT_NOT_READY",
+            "Export is not ready",
+        ));
+    }
+    // 期限切れで削除された直後などはファイルがない
+    let file = state
+        .exporter
+        .download(&job)
+        .await?
+        .ok_or_else(export_job_not_found)?;
+    Ok(export_file_response(file))
+}
+
+/// 認証必須 API（メール未認証でも利用可能）
+/// /me はメール認証状態の確認にも使うため、こちらに置く
 pub fn routes() -> Router<AppState> {
-    Router::new().route("/me", get(me))
+    Router::new()
+        .route("/me", get(me).patch(update_me))
+        .route("/permissions/check", post(check_permissions))
+        .route("/user/withdraw", post(withdraw))
+        .route("/me/sessions", get(list_sessions))
+        .route("/me/sessions/{id}", delete(revoke_session))
+        .route("/me/sessions/revoke-others", post(revoke_other_sessions))
+        .route("/me/accounts", get(list_accounts))
+        .route("/me/accounts/{provider}", delete(unlink_account))
+}
+
+/// 認証 + メール認証済み必須 API
+/// ファイルの保存や個人データの持ち出しなど、本人確認済みのアカウントに限る操作と
+/// ビジネスロジック系の API はこちらに追加する
+pub fn verified_routes() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/me/avatar",
+            // multipart のヘッダー・境界分の余裕を持たせる
+            post(upload_avatar).layer(DefaultBodyLimit::max(avatar::MAX_UPLOAD_BYTES + 64 * 1024)),
+        )
+        .route("/me/export", get(export_me))
+        .route("/me/export/jobs/{id}", get(export_job_status))
+        .route("/me/export/jobs/{id}/download", get(download_export))
 }
@@ -1,7 +1,17 @@
-use axum::{routing::get, Json, Router};
-use serde::Serialize;
+use std::net::SocketAddr;
 
-use crate::middleware::OptionalAuthUser;
+use axum::{
+    extract::{ConnectInfo, State},
+    http::HeaderMap,
+    routing::{get, post},
+    Extension, Json, Router,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::error::ApiError;
+use crate::middleware::{Admin, OptionalAuthUser, RequireAuth, RequireRole, VerifiedUser};
+use crate::rate_limit;
+use crate::withdrawal;
 use crate::AppState;
 
 // ============================================================
@@ -50,8 +60,94 @@ async fn greeting(OptionalAuthUser(user): OptionalAuthUser) -> Json<GreetingResp
     }
 }
 
+// ============================================================
+// 必須認証 API（RequireAuth を使用）
+// - middleware を使わず、公開 API と同じ Router でハンドラ単位に認証必須
+// - 未ログインなら 401
+// ============================================================
+
+/// 必須認証の例: ログインユーザー向けの挨拶 API
+async fn member_greeting(RequireAuth(user): RequireAuth) -> Json<GreetingResponse> {
+    Json(GreetingResponse {
+        message: format!("おかえりなさい、{}さん！", user.name),
+        user_name: Some(user.name),
+        is_logged_in: true,
+    })
+}
+
+/// メール認証済み必須の例: 未認証なら 403（EMAIL_NOT_VERIFIED）
+async fn verified_greeting(VerifiedUser(user): VerifiedUser) -> Json<GreetingResponse> {
+    Json(GreetingResponse {
+        message: format!("{}さん、メール認証ありがとうございます！", user.name),
+        user_name: Some(user.name),
+        is_logged_in: true,
+    })
+}
+
+/// ロール必須の例: admin ロールがなければ 403（FORBIDDEN）
+async fn admin_greeting(RequireRole(user, _): RequireRole<Admin>) -> Json<GreetingResponse> {
+    Json(GreetingResponse {
+        message: format!("管理者の{}さん、こんにちは！", user.name),
+        user_name: Some(user.name),
+        is_logged_in: true,
+    })
+}
+
+// ============================================================
+// 退会したアカウントの復元 API（ログインできないため認証不要）
+// - メールアドレス宛に復元用のリンクを送り、リンクのトークンで復元する
+// ============================================================
+
+#[derive(Deserialize)]
+struct RestoreRequest {
+    email: String,
+}
+
+#[derive(Deserialize)]
+struct RestoreConfirmRequest {
+    token: String,
+}
+
+#[derive(Serialize)]
+struct RestoreResponse {
+    success: bool,
+}
+
+/// 復元用のリンクをメールで送信（該当するユーザーがいなくても success を返す）
+async fn request_restore(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    connect_info: Option<Extension<ConnectInfo<SocketAddr>>>,
+    Json(body): Json<RestoreRequest>,
+) -> Result<Json<RestoreResponse>, ApiError> {
+    if body.email.trim().is_empty() {
+        return Err(ApiError::validation("email is required"));
+    }
+    let ip = rate_limit::client_ip(
+        &headers,
+        connect_info.map(|Extension(ConnectInfo(addr))| addr),
+        state.auth_config.trusted_proxy_hops,
+    );
+    withdrawal::request_restore(&state, &body.email, ip).await?;
+    Ok(Json(RestoreResponse { success: true }))
+}
+
+/// メールのリンクのトークンで復元
+async fn confirm_restore(
+    State(state): State<AppState>,
+    Json(body): Json<RestoreConfirmRequest>,
+) -> Result<Json<RestoreResponse>, ApiError> {
+    withdrawal::confirm_restore(&state, &body.token).await?;
+    Ok(Json(RestoreResponse { success: true }))
+}
+
 pub fn routes() -> Router<AppState> {
     Router::new()
         .route("/health", get(health))
         .route("/greeting", get(greeting)) // 任意認証 API
+        .route("/greeting/member", get(member_greeting)) // 必須認証 API（extractor）
+        .route("/greeting/verified", get(verified_greeting)) // メール認証済み必須 API（extractor）
+        .route("/greeting/admin", get(admin_greeting)) // admin ロール必須 API（extractor）
+        .route("/account/restore", post(request_restore))
+        .route("/account/restore/confirm", post(confirm_restore))
 }
@@ -0,0 +1,295 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use moka::sync::Cache;
+use sea_orm::{sqlx::postgres::PgListener, DatabaseConnection};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use crate::middleware::AuthUser;
+
+/// キャッシュ無効化の NOTIFY チャンネル名（migration のトリガーと同じ値）
+pub const INVALIDATION_CHANNEL: &str = "auth_cache_invalidation";
+
+/// キャッシュに保持する検証済みセッション
+#[derive(Clone, Debug)]
+pub struct CachedSession {
+    pub user: AuthUser,
+    /// sessions.expires_at（期限切れのエントリは TTL 内でも使わない）
+    pub expires_at: DateTime<Utc>,
+}
+
+/// 検証済みセッションのインメモリキャッシュ
+///
+/// - キーはセッショントークンの SHA-256（トークン自体はメモリに残さない）
+/// - 件数上限 + TTL 付き
+/// - 他インスタンスでの変更は Postgres の LISTEN/NOTIFY で無効化
+#[derive(Clone)]
+pub struct SessionCache {
+    /// TTL が 0 の場合はキャッシュ無効（None）
+    inner: Option<Cache<String, CachedSession>>,
+}
+
+/// NOTIFY で受け取る無効化イベント
+#[derive(Deserialize)]
+#[serde(tag = "kind", content = "key", rename_all = "lowercase")]
+enum Invalidation {
+    /// トークンの SHA-256（hex）
+    Session(String),
+    /// ユーザー ID
+    User(String),
+}
+
+impl SessionCache {
+    pub fn new(max_capacity: u64, ttl: Duration) -> Self {
+        if ttl.is_zero() || max_capacity == 0 {
+            return Self { inner: None };
+        }
+
+        let cache = Cache::builder()
+            .max_capacity(max_capacity)
+            .time_to_live(ttl)
+            .support_invalidation_closures()
+            .build();
+
+        Self { inner: Some(cache) }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.inner.is_some()
+    }
+
+    /// セッショントークンからキャッシュキー（SHA-256 の hex）を生成
+    pub fn key(token: &str) -> String {
+        Sha256::digest(token.as_bytes())
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect()
+    }
+
+    pub fn get(&self, key: &str) -> Option<CachedSession> {
+        let cache = self.inner.as_ref()?;
+        let entry = cache.get(key)?;
+
+        // セッション自体の有効期限が切れていれば破棄
+        if entry.expires_at < Utc::now() {
+            cache.invalidate(key);
+            return None;
+        }
+        Some(entry)
+    }
+
+    pub fn insert(&self, key: String, entry: CachedSession) {
+        if let Some(cache) = &self.inner {
+            cache.insert(key, entry);
+        }
+    }
+
+    pub fn invalidate_session(&self, key: &str) {
+        if let Some(cache) = &self.inner {
+            cache.invalidate(key);
+        }
+    }
+
+    pub fn invalidate_user(&self, user_id: &str) {
+        if let Some(cache) = &self.inner {
+            let user_id = user_id.to_string();
+            if let Err(e) = cache.invalidate_entries_if(move |_, entry| entry.user.id == user_id) {
+                // 無効化できない場合は安全側に倒して全件破棄
+                tracing::warn!("Failed to invalidate cached sessions by user: {}", e);
+                cache.invalidate_all();
+            }
+        }
+    }
+
+    pub fn invalidate_all(&self) {
+        if let Some(cache) = &self.inner {
+            cache.invalidate_all();
+        }
+    }
+
+    fn apply(&self, payload: &str) {
+        match serde_json::from_str::<Invalidation>(payload) {
+            Ok(Invalidation::Session(key)) => self.invalidate_session(&key),
+            Ok(Invalidation::User(user_id)) => self.invalidate_user(&user_id),
+            Err(e) => {
+                tracing::warn!("Unknown cache invalidation payload: {}", e);
+                self.invalidate_all();
+            }
+        }
+    }
+}
+
+/// Postgres の NOTIFY を購読し、キャッシュを無効化するタスクを起動
+///
+/// 接続が切れている間の通知は失われるため、切断を検知したら全件破棄する
+pub fn spawn_invalidation_listener(db: &DatabaseConnection, cache: SessionCache) {
+    if !cache.is_enabled() {
+        return;
+    }
+
+    let pool = db.get_postgres_connection_pool().clone();
+
+    tokio::spawn(async move {
+        loop {
+            let mut listener = match PgListener::connect_with(&pool).await {
+                Ok(listener) => listener,
+                Err(e) => {
+                    tracing::error!("Failed to connect cache invalidation listener: {}", e);
+                    cache.invalidate_all();
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                    continue;
+                }
+            };
+
+            if let Err(e) = listener.listen(INVALIDATION_CHANNEL).await {
+                tracing::error!("Failed to LISTEN {}: {}", INVALIDATION_CHANNEL, e);
+                cache.invalidate_all();
+                tokio::time::sleep(Duration::from_secs(5)).await;
+                continue;
+            }
+
+            tracing::info!("Listening for session cache invalidation");
+
+            loop {
+                match listener.try_recv().await {
+                    Ok(Some(notification)) => cache.apply(notification.payload()),
+                    // 接続断（次の try_recv で再接続・再 LISTEN される）
+                    Ok(None) => {
+                        tracing::warn!("Cache invalidation listener disconnected, clearing cache");
+                        cache.invalidate_all();
+                    }
+                    Err(e) => {
+                        tracing::error!("Cache invalidation listener error: {}", e);
+                        cache.invalidate_all();
+                        break;
+                    }
+                }
+            }
+
+            tokio::time::sleep(Duration::from_secs(1)).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::device::DeviceInfo;
+
+    fn cache() -> SessionCache {
+        SessionCache::new(100, Duration::from_secs(60))
+    }
+
+    fn entry(user_id: &str, expires_in: chrono::Duration) -> CachedSession {
+        CachedSession {
+            user: AuthUser {
+                id: user_id.into(),
+                name: "User".into(),
+                email: format!("{user_id}@example.com"),
+                email_verified: true,
+                image: None,
+                roles: vec!["user".into()],
+                updated_at: Utc::now().fixed_offset(),
+                session_id: format!("{user_id}-session"),
+                device: DeviceInfo::parse(None),
+                impersonated_by: None,
+            },
+            expires_at: Utc::now() + expires_in,
+        }
+    }
+
+    /// user-1 の 2 セッションと user-2 の 1 セッションを入れたキャッシュ
+    fn populated() -> (SessionCache, [String; 3]) {
+        let cache = cache();
+        let keys = [
+            SessionCache::key("token-1a"),
+            SessionCache::key("token-1b"),
+            SessionCache::key("token-2"),
+        ];
+        for (key, user_id) in keys.iter().zip(["user-1", "user-1", "user-2"]) {
+            cache.insert(key.clone(), entry(user_id, chrono::Duration::days(1)));
+        }
+        (cache, keys)
+    }
+
+    fn cached(cache: &SessionCache, keys: &[String]) -> Vec<bool> {
+        keys.iter().map(|key| cache.get(key).is_some()).collect()
+    }
+
+    #[test]
+    fn key_is_sha256_of_the_token() {
+        assert_eq!(
+            SessionCache::key("abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn get_drops_sessions_past_expires_at_within_the_ttl() {
+        let (cache, keys) = populated();
+        let expired = SessionCache::key("expired");
+        cache.insert(
+            expired.clone(),
+            entry("user-1", -chrono::Duration::seconds(1)),
+        );
+
+        assert!(cache.get(&expired).is_none());
+        assert!(!cache.inner.as_ref().unwrap().contains_key(&expired));
+        assert_eq!(cached(&cache, &keys), [true, true, true]);
+    }
+
+    #[test]
+    fn invalidate_user_drops_only_that_users_sessions() {
+        let (cache, keys) = populated();
+
+        cache.invalidate_user("user-1");
+
+        assert_eq!(cached(&cache, &keys), [false, false, true]);
+    }
+
+    #[test]
+    fn apply_session_payload_drops_the_session() {
+        let (cache, keys) = populated();
+
+        cache.apply(&format!(r#"{{"kind":"session","key":"{}"}}"#, keys[0]));
+
+        assert_eq!(cached(&cache, &keys), [false, true, true]);
+    }
+
+    #[test]
+    fn apply_user_payload_drops_the_users_sessions() {
+        let (cache, keys) = populated();
+
+        cache.apply(r#"{"kind":"user","key":"user-2"}"#);
+
+        assert_eq!(cached(&cache, &keys), [true, true, false]);
+    }
+
+    #[test]
+    fn apply_unknown_payload_clears_the_cache() {
+        for payload in [
+            "",
+            "not json",
+            r#"{"kind":"tenant","key":"t-1"}"#,
+            r#"{"kind":"user"}"#,
+        ] {
+            let (cache, keys) = populated();
+
+            cache.apply(payload);
+
+            assert_eq!(cached(&cache, &keys), [false, false, false], "{payload}");
+        }
+    }
+
+    #[test]
+    fn zero_ttl_disables_the_cache() {
+        let cache = SessionCache::new(100, Duration::ZERO);
+        let key = SessionCache::key("token");
+
+        cache.insert(key.clone(), entry("user-1", chrono::Duration::days(1)));
+
+        assert!(!cache.is_enabled());
+        assert!(cache.get(&key).is_none());
+    }
+}
@@ -0,0 +1,362 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Datelike, Days, NaiveDate, NaiveTime, Utc};
+use sea_orm::{
+    sea_query::Expr, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait,
+    JoinType, QueryFilter, QueryOrder, QuerySelect,
+};
+use serde::Serialize;
+
+use crate::entity::{accounts, auth_daily_stats, sessions, user_events, users};
+use crate::AppState;
+
+// ============================================================
+// 管理者向けの統計（GET /api/admin/stats）
+// - 集計は SQL の GROUP BY で行い、ユーザーやセッションの行は読み込まない
+// - 日付は UTC で区切る（週は月曜始まり）
+// - 登録数・メール認証状況・退会数は期間で絞り込み、セッション数は現在の値
+// - 登録数・退会数は user_events（users のトリガーで記録）から数える。
+//   猶予期間後に完全削除されたユーザーも含まれる
+// - メール認証状況は退会していないユーザーの現在の値
+// - STATS_ROLLUP_REFRESH_SECONDS を設定した場合、期間で絞り込む値はロールアップ
+//   （auth_daily_stats マテリアライズドビュー）から読む。最大で更新間隔ぶん古い値になる
+// ============================================================
+
+/// 集計する期間（UTC の日付、両端を含む）
+pub struct StatsRange {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+}
+
+impl StatsRange {
+    /// 期間の開始・終了時刻（終了は含まない）
+    fn bounds(&self) -> (DateTime<Utc>, DateTime<Utc>) {
+        let end = self.to.succ_opt().unwrap_or(NaiveDate::MAX);
+        (
+            self.from.and_time(NaiveTime::MIN).and_utc(),
+            end.and_time(NaiveTime::MIN).and_utc(),
+        )
+    }
+}
+
+#[derive(Serialize)]
+pub struct DailyCount {
+    pub date: NaiveDate,
+    pub count: i64,
+}
+
+#[derive(Serialize)]
+pub struct WeeklyCount {
+    /// 週の初日（月曜日。期間の開始日より前の場合がある）
+    pub week_start: NaiveDate,
+    pub count: i64,
+}
+
+/// 期間内に登録したユーザー（退会済みを除く）のメール認証状況
+#[derive(Serialize)]
+pub struct VerificationStats {
+    pub verified: i64,
+    pub unverified: i64,
+    /// verified / (verified + unverified)。対象がいない場合は null
+    pub verified_ratio: Option<f64>,
+}
+
+/// 有効期限内のセッション（なりすましセッションを除く）
+#[derive(Serialize)]
+pub struct ActiveSessionStats {
+    pub sessions: i64,
+    pub users: i64,
+}
+
+/// ログイン方法ごとの有効なセッション数
+/// セッションにはログインに使った方法が記録されないため、ユーザーが連携している
+/// ログイン方法（accounts.provider_id）ごとに数える（複数連携のユーザーはそれぞれに含まれる）
+#[derive(Serialize)]
+pub struct ProviderSessions {
+    pub provider: String,
+    pub sessions: i64,
+}
+
+#[derive(Serialize)]
+pub struct Stats {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    /// 期間で絞り込む値の集計元（"live": 都度集計 / "rollup": auth_daily_stats）
+    pub source: &'static str,
+    pub signups_per_day: Vec<DailyCount>,
+    pub email_verification: VerificationStats,
+    pub active_sessions: ActiveSessionStats,
+    pub sessions_per_provider: Vec<ProviderSessions>,
+    pub withdrawals_per_week: Vec<WeeklyCount>,
+}
+
+/// 統計を集計
+pub async fn collect(state: &AppState, range: &StatsRange) -> Result<Stats, DbErr> {
+    let db = state.db.as_ref();
+    let rollup = !state.auth_config.stats_rollup_refresh_interval.is_zero();
+
+    let (signups, (verified, unverified), withdrawals, active_sessions, sessions_per_provider) =
+        if rollup {
+            tokio::try_join!(
+                rollup_signups(db, range),
+                rollup_verification(db, range),
+                rollup_withdrawals(db, range),
+                active_sessions(db),
+                sessions_per_provider(db),
+            )?
+        } else {
+            tokio::try_join!(
+                live_signups(db, range),
+                live_verification(db, range),
+                live_withdrawals(db, range),
+                active_sessions(db),
+                sessions_per_provider(db),
+            )?
+        };
+
+    let total = verified + unverified;
+    Ok(Stats {
+        from: range.from,
+        to: range.to,
+        source: if rollup { "rollup" } else { "live" },
+        signups_per_day: daily_series(range, signups),
+        email_verification: VerificationStats {
+            verified,
+            unverified,
+            verified_ratio: (total > 0).then(|| verified as f64 / total as f64),
+        },
+        active_sessions,
+        sessions_per_provider,
+        withdrawals_per_week: weekly_series(range, withdrawals),
+    })
+}
+
+/// 件数のない日を 0 で埋める
+fn daily_series(range: &StatsRange, counts: Vec<(NaiveDate, i64)>) -> Vec<DailyCount> {
+    let counts: HashMap<_, _> = counts.into_iter().collect();
+    range
+        .from
+        .iter_days()
+        .take_while(|date| *date <= range.to)
+        .map(|date| DailyCount {
+            date,
+            count: counts.get(&date).copied().unwrap_or(0),
+        })
+        .collect()
+}
+
+/// 件数のない週を 0 で埋める
+fn weekly_series(range: &StatsRange, counts: Vec<(NaiveDate, i64)>) -> Vec<WeeklyCount> {
+    let counts: HashMap<_, _> = counts.into_iter().collect();
+    let first = range.from - Days::new(range.from.weekday().num_days_from_monday().into());
+    first
+        .iter_weeks()
+        .take_while(|week_start| *week_start <= range.to)
+        .map(|week_start| WeeklyCount {
+            week_start,
+            count: counts.get(&week_start).copied().unwrap_or(0),
+        })
+        .collect()
+}
+
+// ============================================================
+// 都度集計（user_events / users）
+// ============================================================
+
+/// user_events の種類
+const SIGNUP: &str = "signup";
+const WITHDRAWAL: &str = "withdrawal";
+
+async fn live_signups<C: ConnectionTrait>(
+    db: &C,
+    range: &StatsRange,
+) -> Result<Vec<(NaiveDate, i64)>, DbErr> {
+    let (start, end) = range.bounds();
+    let day = Expr::cust("(created_at AT TIME ZONE 'UTC')::date");
+
+    user_events::Entity::find()
+        .select_only()
+        .column_as(day.clone(), "day")
+        .column_as(Expr::col(user_events::Column::Id).count(), "count")
+        .filter(user_events::Column::Kind.eq(SIGNUP))
+        .filter(user_events::Column::CreatedAt.gte(start))
+        .filter(user_events::Column::CreatedAt.lt(end))
+        .group_by(day)
+        .into_tuple()
+        .all(db)
+        .await
+}
+
+async fn live_verification<C: ConnectionTrait>(
+    db: &C,
+    range: &StatsRange,
+) -> Result<(i64, i64), DbErr> {
+    let (start, end) = range.bounds();
+
+    let counts: Vec<(bool, i64)> = users::Entity::find()
+        .select_only()
+        .column(users::Column::EmailVerified)
+        .column_as(Expr::col(users::Column::Id).count(), "count")
+        .filter(users::Column::CreatedAt.gte(start))
+        .filter(users::Column::CreatedAt.lt(end))
+        .filter(users::Column::DeletedAt.is_null())
+        .group_by(users::Column::EmailVerified)
+        .into_tuple()
+        .all(db)
+        .await?;
+
+    let count = |verified: bool| {
+        counts
+            .iter()
+            .find(|(v, _)| *v == verified)
+            .map_or(0, |(_, count)| *count)
+    };
+    Ok((count(true), count(false)))
+}
+
+async fn live_withdrawals<C: ConnectionTrait>(
+    db: &C,
+    range: &StatsRange,
+) -> Result<Vec<(NaiveDate, i64)>, DbErr> {
+    let (start, end) = range.bounds();
+    let week = Expr::cust("date_trunc('week', created_at AT TIME ZONE 'UTC')::date");
+
+    user_events::Entity::find()
+        .select_only()
+        .column_as(week.clone(), "week")
+        .column_as(Expr::col(user_events::Column::Id).count(), "count")
+        .filter(user_events::Column::Kind.eq(WITHDRAWAL))
+        .filter(user_events::Column::CreatedAt.gte(start))
+        .filter(user_events::Column::CreatedAt.lt(end))
+        .group_by(week)
+        .into_tuple()
+        .all(db)
+        .await
+}
+
+// ============================================================
+// ロールアップ（auth_daily_stats）
+// ============================================================
+
+async fn rollup_signups<C: ConnectionTrait>(
+    db: &C,
+    range: &StatsRange,
+) -> Result<Vec<(NaiveDate, i64)>, DbErr> {
+    auth_daily_stats::Entity::find()
+        .select_only()
+        .column(auth_daily_stats::Column::Day)
+        .column(auth_daily_stats::Column::Signups)
+        .filter(auth_daily_stats::Column::Day.between(range.from, range.to))
+        .filter(auth_daily_stats::Column::Signups.gt(0))
+        .into_tuple()
+        .all(db)
+        .await
+}
+
+async fn rollup_verification<C: ConnectionTrait>(
+    db: &C,
+    range: &StatsRange,
+) -> Result<(i64, i64), DbErr> {
+    let counts = auth_daily_stats::Entity::find()
+        .select_only()
+        .column_as(Expr::cust("coalesce(sum(verified), 0)::bigint"), "verified")
+        .column_as(
+            Expr::cust("coalesce(sum(unverified), 0)::bigint"),
+            "unverified",
+        )
+        .filter(auth_daily_stats::Column::Day.between(range.from, range.to))
+        .into_tuple()
+        .one(db)
+        .await?;
+
+    Ok(counts.unwrap_or_default())
+}
+
+async fn rollup_withdrawals<C: ConnectionTrait>(
+    db: &C,
+    range: &StatsRange,
+) -> Result<Vec<(NaiveDate, i64)>, DbErr> {
+    let week = Expr::cust("date_trunc('week', day)::date");
+
+    auth_daily_stats::Entity::find()
+        .select_only()
+        .column_as(week.clone(), "week")
+        .column_as(Expr::cust("sum(withdrawals)::bigint"), "count")
+        .filter(auth_daily_stats::Column::Day.between(range.from, range.to))
+        .filter(auth_daily_stats::Column::Withdrawals.gt(0))
+        .group_by(week)
+        .into_tuple()
+        .all(db)
+        .await
+}
+
+// ============================================================
+// セッション（現在の値）
+// ============================================================
+
+async fn active_sessions<C: ConnectionTrait>(db: &C) -> Result<ActiveSessionStats, DbErr> {
+    let counts: Option<(i64, i64)> = sessions::Entity::find()
+        .select_only()
+        .column_as(Expr::col(sessions::Column::Id).count(), "sessions")
+        .column_as(Expr::cust("count(DISTINCT user_id)"), "users")
+        .filter(sessions::Column::ExpiresAt.gt(Utc::now()))
+        .filter(sessions::Column::ImpersonatedBy.is_null())
+        .into_tuple()
+        .one(db)
+        .await?;
+
+    let (sessions, users) = counts.unwrap_or_default();
+    Ok(ActiveSessionStats { sessions, users })
+}
+
+async fn sessions_per_provider<C: ConnectionTrait>(db: &C) -> Result<Vec<ProviderSessions>, DbErr> {
+    let counts: Vec<(String, i64)> = accounts::Entity::find()
+        .select_only()
+        .column(accounts::Column::ProviderId)
+        .column_as(Expr::cust(r#"count(DISTINCT "sessions"."id")"#), "sessions")
+        .join(
+            JoinType::InnerJoin,
+            accounts::Entity::belongs_to(sessions::Entity)
+                .from(accounts::Column::UserId)
+                .to(sessions::Column::UserId)
+                .into(),
+        )
+        .filter(sessions::Column::ExpiresAt.gt(Utc::now()))
+        .filter(sessions::Column::ImpersonatedBy.is_null())
+        .group_by(accounts::Column::ProviderId)
+        .order_by_asc(accounts::Column::ProviderId)
+        .into_tuple()
+        .all(db)
+        .await?;
+
+    Ok(counts
+        .into_iter()
+        .map(|(provider, sessions)| ProviderSessions { provider, sessions })
+        .collect())
+}
+
+/// ロールアップを定期的に更新（STATS_ROLLUP_REFRESH_SECONDS が 0 なら何もしない）
+pub fn spawn_rollup_job(state: AppState) {
+    let interval = state.auth_config.stats_rollup_refresh_interval;
+    if interval.is_zero() {
+        tracing::info!("STATS_ROLLUP_REFRESH_SECONDS is 0, stats are aggregated on each request");
+        return;
+    }
+
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = refresh_rollup(state.db.as_ref()).await {
+                tracing::error!("Failed to refresh auth_daily_stats: {}", e);
+            }
+        }
+    });
+}
+
+async fn refresh_rollup(db: &DatabaseConnection) -> Result<(), DbErr> {
+    // CONCURRENTLY: 更新中も統計 API から読めるようにする
+    db.execute_unprepared("REFRESH MATERIALIZED VIEW CONCURRENTLY auth_daily_stats")
+        .await?;
+    Ok(())
+}
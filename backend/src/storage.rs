@@ -0,0 +1,505 @@
+use std::env;
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use axum::Router;
+use tower_http::services::ServeDir;
+
+use crate::config::ConfigError;
+
+// ============================================================
+// ファイル保存（アバター画像など）
+// - BlobStore トレイトで保存先を差し替え可能
+// - BLOB_STORE=local（デフォルト）: ローカルのディレクトリに保存し、/uploads で配信
+// - BLOB_STORE=s3: S3 互換ストレージに保存（Cargo の s3 feature が必要）
+//   AWS_ENDPOINT を指定すれば MinIO などのローカル環境でも動作する
+// - ローカル保存で配信するのは指定したプレフィックス（アバター画像）のみ
+//   PRIVATE_PREFIX 配下（エクスポートファイルなど）は公開せず、API 経由でのみ返す
+// ============================================================
+
+/// ローカル保存時の配信パス
+pub const LOCAL_SERVE_PATH: &str = "/uploads";
+
+/// 公開しないファイルの key のプレフィックス
+/// ローカル保存では配信の対象外、S3 ではバケットの公開設定の対象外にすること
+pub const PRIVATE_PREFIX: &str = "private";
+
+const DEFAULT_LOCAL_DIR: &str = "./uploads";
+const DEFAULT_PUBLIC_BASE_URL: &str = "http://localhost:3051/uploads";
+
+#[derive(Debug)]
+pub struct BlobStoreError(String);
+
+impl BlobStoreError {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self(message.into())
+    }
+}
+
+impl fmt::Display for BlobStoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// ファイルの保存先
+#[async_trait]
+pub trait BlobStore: Send + Sync {
+    /// key（"avatars/{user_id}/256.png" など）に保存し、公開 URL を返す
+    /// 同じ key が存在する場合は上書きする
+    async fn put(
+        &self,
+        key: &str,
+        data: Vec<u8>,
+        content_type: &str,
+    ) -> Result<String, BlobStoreError>;
+
+    /// key のファイルを読み込む（存在しない場合は None）
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, BlobStoreError>;
+
+    /// key のファイルを削除（存在しない場合も成功）
+    async fn delete(&self, key: &str) -> Result<(), BlobStoreError>;
+}
+
+/// BLOB_STORE の値から実装を選択（未設定は local）
+pub fn blob_store_from_env() -> Result<Arc<dyn BlobStore>, ConfigError> {
+    let public_base_url = env::var("BLOB_PUBLIC_BASE_URL")
+        .unwrap_or_else(|_| DEFAULT_PUBLIC_BASE_URL.into())
+        .trim_end_matches('/')
+        .to_string();
+
+    let name = env::var("BLOB_STORE").unwrap_or_else(|_| "local".into());
+    match name.as_str() {
+        "local" => Ok(Arc::new(LocalBlobStore {
+            root: local_dir(),
+            public_base_url,
+        })),
+        #[cfg(feature = "s3")]
+        "s3" => Ok(Arc::new(S3BlobStore::from_env(public_base_url)?)),
+        other => Err(ConfigError::new(
+            "BLOB_STORE",
+            format!("must be local or s3 with the s3 feature (got {other:?})"),
+        )),
+    }
+}
+
+/// ローカル保存の場合に /uploads で配信するルーター（local 以外は None）
+/// public_prefixes 配下のディレクトリだけをルーティングする。ServeDir は %xx のデコードや
+/// "//"・"./" の正規化をルーティングの後に行うため、除外するパスを指定する方式にはしない
+pub fn local_serve_router<S>(public_prefixes: &[&str]) -> Option<Router<S>>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    match env::var("BLOB_STORE").as_deref() {
+        Ok("local") | Err(_) => Some(serve_router(&local_dir(), public_prefixes)),
+        Ok(_) => None,
+    }
+}
+
+fn serve_router<S>(root: &Path, public_prefixes: &[&str]) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    public_prefixes
+        .iter()
+        .fold(Router::new(), |router, prefix| {
+            router.nest_service(&format!("/{prefix}"), ServeDir::new(root.join(prefix)))
+        })
+}
+
+fn local_dir() -> PathBuf {
+    env::var("BLOB_STORE_LOCAL_DIR")
+        .unwrap_or_else(|_| DEFAULT_LOCAL_DIR.into())
+        .into()
+}
+
+/// key が "a/b/c.png" のような相対パスのみで構成されているか
+/// （".." や絶対パスでディレクトリ外に書き込まないようにする）
+fn is_safe_key(key: &str) -> bool {
+    !key.is_empty()
+        && Path::new(key)
+            .components()
+            .all(|c| matches!(c, Component::Normal(_)))
+}
+
+// ============================================================
+// ローカルファイルシステム
+// ============================================================
+
+pub struct LocalBlobStore {
+    root: PathBuf,
+    public_base_url: String,
+}
+
+#[async_trait]
+impl BlobStore for LocalBlobStore {
+    async fn put(
+        &self,
+        key: &str,
+        data: Vec<u8>,
+        _content_type: &str,
+    ) -> Result<String, BlobStoreError> {
+        if !is_safe_key(key) {
+            return Err(BlobStoreError(format!("Invalid key: {key}")));
+        }
+
+        let path = self.root.join(key);
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await.map_err(|e| {
+                BlobStoreError(format!("Failed to create {}: {e}", parent.display()))
+            })?;
+        }
+
+        // 書き込み途中のファイルが配信されないよう、一時ファイルに書いてから置き換える
+        // 同じ key への同時アップロードで一時ファイルが衝突しないよう、名前にランダムな値を付ける
+        let tmp = path.with_extension(format!("{}.tmp", uuid::Uuid::new_v4()));
+        tokio::fs::write(&tmp, data)
+            .await
+            .map_err(|e| BlobStoreError(format!("Failed to write {}: {e}", tmp.display())))?;
+        tokio::fs::rename(&tmp, &path)
+            .await
+            .map_err(|e| BlobStoreError(format!("Failed to write {}: {e}", path.display())))?;
+
+        Ok(format!("{}/{}", self.public_base_url, key))
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, BlobStoreError> {
+        if !is_safe_key(key) {
+            return Err(BlobStoreError(format!("Invalid key: {key}")));
+        }
+
+        let path = self.root.join(key);
+        match tokio::fs::read(&path).await {
+            Ok(data) => Ok(Some(data)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(BlobStoreError(format!(
+                "Failed to read {}: {e}",
+                path.display()
+            ))),
+        }
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), BlobStoreError> {
+        if !is_safe_key(key) {
+            return Err(BlobStoreError(format!("Invalid key: {key}")));
+        }
+
+        let path = self.root.join(key);
+        match tokio::fs::remove_file(&path).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(BlobStoreError(format!(
+                "Failed to delete {}: {e}",
+                path.display()
+            ))),
+        }
+    }
+}
+
+// ============================================================
+// メモリ（テスト用）
+// ============================================================
+
+/// 保存した内容をメモリに持つ BlobStore（テストで保存内容を確認するために使う）
+#[cfg(test)]
+#[derive(Default)]
+pub(crate) struct MemoryBlobStore(
+    pub(crate) std::sync::Mutex<std::collections::HashMap<String, Vec<u8>>>,
+);
+
+#[cfg(test)]
+#[async_trait]
+impl BlobStore for MemoryBlobStore {
+    async fn put(
+        &self,
+        key: &str,
+        data: Vec<u8>,
+        _content_type: &str,
+    ) -> Result<String, BlobStoreError> {
+        self.0.lock().unwrap().insert(key.into(), data);
+        Ok(format!("http://localhost/uploads/{key}"))
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, BlobStoreError> {
+        Ok(self.0.lock().unwrap().get(key).cloned())
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), BlobStoreError> {
+        self.0.lock().unwrap().remove(key);
+        Ok(())
+    }
+}
+
+// ============================================================
+// S3 互換ストレージ（feature = "s3"）
+// - 接続情報は AWS_BUCKET / AWS_REGION / AWS_ENDPOINT / AWS_ACCESS_KEY_ID /
+//   AWS_SECRET_ACCESS_KEY / AWS_ALLOW_HTTP から読み込む
+// - BLOB_PUBLIC_BASE_URL にはバケット（または CDN）の公開 URL を設定する
+// ============================================================
+
+#[cfg(feature = "s3")]
+pub struct S3BlobStore {
+    store: Arc<dyn object_store::ObjectStore>,
+    public_base_url: String,
+}
+
+#[cfg(feature = "s3")]
+impl S3BlobStore {
+    /// 接続先を指定して作成（テストではインメモリの ObjectStore を渡す）
+    pub fn new(store: Arc<dyn object_store::ObjectStore>, public_base_url: String) -> Self {
+        Self {
+            store,
+            public_base_url,
+        }
+    }
+
+    fn from_env(public_base_url: String) -> Result<Self, ConfigError> {
+        let store = object_store::aws::AmazonS3Builder::from_env()
+            .build()
+            .map_err(|e| {
+                ConfigError::new(
+                    "BLOB_STORE",
+                    format!("is s3 but the AWS_* settings are invalid: {e}"),
+                )
+            })?;
+        Ok(Self::new(Arc::new(store), public_base_url))
+    }
+}
+
+#[cfg(feature = "s3")]
+#[async_trait]
+impl BlobStore for S3BlobStore {
+    async fn put(
+        &self,
+        key: &str,
+        data: Vec<u8>,
+        content_type: &str,
+    ) -> Result<String, BlobStoreError> {
+        use object_store::{Attribute, Attributes, ObjectStore, PutOptions};
+
+        if !is_safe_key(key) {
+            return Err(BlobStoreError(format!("Invalid key: {key}")));
+        }
+
+        let options = PutOptions {
+            attributes: Attributes::from_iter([(Attribute::ContentType, content_type.to_string())]),
+            ..Default::default()
+        };
+        self.store
+            .put_opts(&object_store::path::Path::from(key), data.into(), options)
+            .await
+            .map_err(|e| BlobStoreError(format!("Failed to upload {key}: {e}")))?;
+
+        Ok(format!("{}/{}", self.public_base_url, key))
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, BlobStoreError> {
+        use object_store::ObjectStore;
+
+        if !is_safe_key(key) {
+            return Err(BlobStoreError(format!("Invalid key: {key}")));
+        }
+
+        let result = match self.store.get(&object_store::path::Path::from(key)).await {
+            Ok(result) => result,
+            Err(object_store::Error::NotFound { .. }) => return Ok(None),
+            Err(e) => return Err(BlobStoreError(format!("Failed to download {key}: {e}"))),
+        };
+        let data = result
+            .bytes()
+            .await
+            .map_err(|e| BlobStoreError(format!("Failed to download {key}: {e}")))?;
+        Ok(Some(data.to_vec()))
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), BlobStoreError> {
+        use object_store::ObjectStore;
+
+        if !is_safe_key(key) {
+            return Err(BlobStoreError(format!("Invalid key: {key}")));
+        }
+
+        match self
+            .store
+            .delete(&object_store::path::Path::from(key))
+            .await
+        {
+            Ok(()) | Err(object_store::Error::NotFound { .. }) => Ok(()),
+            Err(e) => Err(BlobStoreError(format!("Failed to delete {key}: {e}"))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        body::Body,
+        http::{Request, StatusCode},
+    };
+    use tower::ServiceExt;
+
+    use super::*;
+
+    /// テスト用の一時ディレクトリ（終了時に削除）
+    struct TestDir(PathBuf);
+
+    impl TestDir {
+        fn new() -> Self {
+            let dir = env::temp_dir().join(format!("blob-store-test-{}", uuid::Uuid::new_v4()));
+            std::fs::create_dir_all(&dir).unwrap();
+            Self(dir)
+        }
+
+        fn write(&self, key: &str, data: &[u8]) {
+            let path = self.0.join(key);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, data).unwrap();
+        }
+    }
+
+    impl Drop for TestDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    async fn status(router: &Router, uri: &str) -> StatusCode {
+        let request = Request::builder().uri(uri).body(Body::empty()).unwrap();
+        router.clone().oneshot(request).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn serves_only_public_prefixes() {
+        let dir = TestDir::new();
+        dir.write("avatars/user-1/256.png", b"png");
+        dir.write("private/exports/user-1/export.json", b"{}");
+
+        let router = Router::new().nest(LOCAL_SERVE_PATH, serve_router(&dir.0, &["avatars"]));
+
+        assert_eq!(
+            status(&router, "/uploads/avatars/user-1/256.png").await,
+            StatusCode::OK
+        );
+
+        for uri in [
+            "/uploads/private/exports/user-1/export.json",
+            "/uploads/%70rivate/exports/user-1/export.json",
+            "/uploads//private/exports/user-1/export.json",
+            "/uploads/./private/exports/user-1/export.json",
+            "/uploads/avatars/../private/exports/user-1/export.json",
+            "/uploads/avatars/%2e%2e/private/exports/user-1/export.json",
+            "/uploads/avatars/..%2fprivate/exports/user-1/export.json",
+        ] {
+            assert_eq!(status(&router, uri).await, StatusCode::NOT_FOUND, "{uri}");
+        }
+    }
+
+    /// 保存・読み込み・削除の一連の動作（実装によらず共通）
+    async fn assert_put_get_delete(store: &dyn BlobStore, public_base_url: &str) {
+        let key = "avatars/user-1/256.png";
+
+        assert_eq!(store.get(key).await.unwrap(), None);
+
+        let url = store
+            .put(key, b"first".to_vec(), "image/png")
+            .await
+            .unwrap();
+        assert_eq!(url, format!("{public_base_url}/{key}"));
+        assert_eq!(store.get(key).await.unwrap(), Some(b"first".to_vec()));
+
+        // 同じ key は上書き
+        store
+            .put(key, b"second".to_vec(), "image/png")
+            .await
+            .unwrap();
+        assert_eq!(store.get(key).await.unwrap(), Some(b"second".to_vec()));
+
+        store.delete(key).await.unwrap();
+        assert_eq!(store.get(key).await.unwrap(), None);
+        // 存在しない key の削除も成功
+        store.delete(key).await.unwrap();
+
+        for key in [
+            "",
+            "../escape.png",
+            "/etc/passwd",
+            "avatars/../../escape.png",
+        ] {
+            assert!(store.put(key, vec![], "image/png").await.is_err(), "{key}");
+            assert!(store.get(key).await.is_err(), "{key}");
+            assert!(store.delete(key).await.is_err(), "{key}");
+        }
+    }
+
+    #[tokio::test]
+    async fn local_store_puts_gets_and_deletes() {
+        let dir = TestDir::new();
+        let store = LocalBlobStore {
+            root: dir.0.clone(),
+            public_base_url: "http://localhost/uploads".into(),
+        };
+
+        assert_put_get_delete(&store, "http://localhost/uploads").await;
+        assert!(!dir.0.parent().unwrap().join("escape.png").exists());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn local_store_handles_concurrent_puts_to_the_same_key() {
+        let dir = TestDir::new();
+        let store = Arc::new(LocalBlobStore {
+            root: dir.0.clone(),
+            public_base_url: "http://localhost/uploads".into(),
+        });
+        let key = "avatars/user-1/256.png";
+
+        let uploads: Vec<_> = (0..32u8)
+            .map(|i| {
+                let store = store.clone();
+                tokio::spawn(async move { store.put(key, vec![i; 64 * 1024], "image/png").await })
+            })
+            .collect();
+        for upload in uploads {
+            upload.await.unwrap().unwrap();
+        }
+
+        // いずれか 1 つのアップロードの内容がそのまま残り、一時ファイルは残らない
+        let data = store.get(key).await.unwrap().unwrap();
+        assert_eq!(data.len(), 64 * 1024);
+        assert!(data.iter().all(|&b| b == data[0]));
+        let files: Vec<_> = std::fs::read_dir(dir.0.join("avatars/user-1"))
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        assert_eq!(files, ["256.png"]);
+    }
+
+    #[cfg(feature = "s3")]
+    #[tokio::test]
+    async fn s3_store_puts_gets_and_deletes() {
+        use object_store::{memory::InMemory, Attribute, ObjectStore};
+
+        let memory = Arc::new(InMemory::new());
+        let store = S3BlobStore::new(memory.clone(), "https://cdn.example.com".into());
+
+        assert_put_get_delete(&store, "https://cdn.example.com").await;
+
+        // Content-Type を付けて保存する
+        store
+            .put("avatars/user-1/64.png", b"png".to_vec(), "image/png")
+            .await
+            .unwrap();
+        let result = memory
+            .get(&object_store::path::Path::from("avatars/user-1/64.png"))
+            .await
+            .unwrap();
+        assert_eq!(
+            result
+                .attributes
+                .get(&Attribute::ContentType)
+                .map(|v| v.as_ref()),
+            Some("image/png")
+        );
+    }
+}
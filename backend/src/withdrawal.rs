@@ -0,0 +1,576 @@
+use std::net::IpAddr;
+use std::time::Duration;
+
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+    Aes256Gcm, Key, Nonce,
+};
+use axum::http::StatusCode;
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use chrono::{SubsecRound, Utc};
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use sea_orm::{
+    sea_query::Expr, ActiveModelTrait, ActiveValue::Set, ColumnTrait, ConnectionTrait, DbErr,
+    EntityTrait, IntoActiveModel, ModelTrait, QueryFilter, QueryOrder, QuerySelect,
+    TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+use crate::avatar;
+use crate::config::AuthConfig;
+use crate::entity::{accounts, user_withdrawals, users, verifications};
+use crate::error::ApiError;
+use crate::middleware::AuthError;
+use crate::rate_limit::RateLimiter;
+use crate::AppState;
+
+// ============================================================
+// 退会の猶予期間
+// - 退会時に元のユーザー情報・アカウントを暗号化して user_withdrawals に保存
+// - 猶予期間（WITHDRAWAL_GRACE_PERIOD_DAYS）内なら管理者、
+//   または本人がメールアドレスの確認を経て復元できる
+// - 猶予期間を過ぎたユーザーはバックグラウンドジョブで完全に削除する
+//   （sessions / accounts / user_withdrawals は外部キーの CASCADE で削除）
+// ============================================================
+
+/// 復元用トークンの verifications.identifier のプレフィックス
+/// （Better Auth の "reset-password:{token}" と同じ形式。value はユーザー ID）
+const RESTORE_IDENTIFIER_PREFIX: &str = "restore-account:";
+/// 復元用トークンの有効期間（時間）
+const RESTORE_TOKEN_EXPIRES_IN_HOURS: i64 = 1;
+/// 復元メールの送信依頼の上限（1 時間あたり、メールアドレスごと / IP ごと）
+const RESTORE_REQUESTS_PER_EMAIL: u32 = 3;
+const RESTORE_REQUESTS_PER_IP: u32 = 10;
+const RESTORE_RATE_LIMIT_WINDOW: Duration = Duration::from_secs(60 * 60);
+
+/// 暗号化して保存する退会前のデータ
+#[derive(Serialize, Deserialize)]
+struct WithdrawnUser {
+    email: String,
+    name: String,
+    image: Option<String>,
+    email_verified: bool,
+    accounts: Vec<accounts::Model>,
+}
+
+fn hmac(secret: &str, label: &str, data: &[u8]) -> Vec<u8> {
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(secret.as_bytes())
+        .expect("HMAC can take key of any size");
+    mac.update(label.as_bytes());
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// BETTER_AUTH_SECRET から暗号化キーを導出
+/// （シークレットを変更すると、保存済みのデータは復元できなくなる）
+fn cipher(secret: &str) -> Aes256Gcm {
+    let key = hmac(secret, "withdrawal-encryption-key", &[]);
+    Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key))
+}
+
+/// base64(nonce || ciphertext)
+fn encrypt(secret: &str, data: &WithdrawnUser) -> String {
+    let plaintext = serde_json::to_vec(data).expect("WithdrawnUser must be serializable");
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher(secret)
+        .encrypt(&nonce, plaintext.as_slice())
+        .expect("AES-GCM encryption must not fail");
+
+    let mut encoded = nonce.to_vec();
+    encoded.extend(ciphertext);
+    STANDARD.encode(encoded)
+}
+
+/// テスト用の退会前データ（user_withdrawals.encrypted_data）
+#[cfg(test)]
+pub(crate) fn encrypted_backup(
+    secret: &str,
+    email: &str,
+    accounts: Vec<accounts::Model>,
+) -> String {
+    encrypt(
+        secret,
+        &WithdrawnUser {
+            email: email.into(),
+            name: "Restored User".into(),
+            image: None,
+            email_verified: true,
+            accounts,
+        },
+    )
+}
+
+fn decrypt(secret: &str, encoded: &str) -> Option<WithdrawnUser> {
+    let bytes = STANDARD.decode(encoded).ok()?;
+    if bytes.len() < 12 {
+        return None;
+    }
+    let (nonce, ciphertext) = bytes.split_at(12);
+    let plaintext = cipher(secret)
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .ok()?;
+    serde_json::from_slice(&plaintext).ok()
+}
+
+/// 復元リクエストでメールアドレスから検索するためのハッシュ
+fn email_hash(secret: &str, email: &str) -> String {
+    hex::encode(hmac(
+        secret,
+        "withdrawal-email:",
+        email.trim().to_lowercase().as_bytes(),
+    ))
+}
+
+fn not_found() -> ApiError {
+    ApiError::new(
+        StatusCode::NOT_FOUND,
+        "NOT_FOUND",
+        "Withdrawn user not found",
+    )
+}
+
+/// 退会前のユーザー情報・アカウントを暗号化して保存
+/// 退会処理のトランザクション内で、匿名化・アカウント削除の前に呼ぶ
+pub async fn save_backup<C: ConnectionTrait>(
+    db: &C,
+    config: &AuthConfig,
+    user_id: &str,
+) -> Result<(), ApiError> {
+    // 同時に退会処理が行われた場合に備えて行をロックする
+    let user = users::Entity::find_by_id(user_id)
+        .filter(users::Column::DeletedAt.is_null())
+        .lock_exclusive()
+        .one(db)
+        .await?
+        .ok_or(AuthError::UserWithdrawn)?;
+    let accounts = user.find_related(accounts::Entity).all(db).await?;
+
+    let backup = WithdrawnUser {
+        email: user.email.clone(),
+        name: user.name,
+        image: user.image,
+        email_verified: user.email_verified,
+        accounts,
+    };
+
+    user_withdrawals::ActiveModel {
+        user_id: Set(user.id),
+        email_hash: Set(email_hash(&config.secret, &user.email)),
+        encrypted_data: Set(encrypt(&config.secret, &backup)),
+        created_at: Set(Utc::now().fixed_offset()),
+    }
+    .insert(db)
+    .await?;
+
+    Ok(())
+}
+
+/// 猶予期間内の退会ユーザーを復元
+///
+/// 1. ユーザー情報（メールアドレス・名前・画像）を元に戻し、deleted_at を解除
+/// 2. アカウント（パスワード・OAuth 連携）を元に戻す
+/// 3. 保存していたデータと復元用トークンを削除
+pub async fn restore(state: &AppState, user_id: &str) -> Result<(), ApiError> {
+    let config = &state.auth_config;
+    let txn = state.db.begin().await?;
+
+    let user = users::Entity::find_by_id(user_id)
+        .lock_exclusive()
+        .one(&txn)
+        .await?
+        .ok_or_else(not_found)?;
+    let deleted_at = user.deleted_at.ok_or_else(not_found)?;
+    if deleted_at < Utc::now() - config.withdrawal_grace_period {
+        return Err(not_found());
+    }
+
+    let backup_row = user
+        .find_related(user_withdrawals::Entity)
+        .one(&txn)
+        .await?
+        .ok_or_else(not_found)?;
+    let backup = decrypt(&config.secret, &backup_row.encrypted_data).ok_or_else(|| {
+        tracing::error!("Failed to decrypt withdrawal backup: {}", user.id);
+        ApiError::internal("Internal server error")
+    })?;
+
+    // 退会後に同じメールアドレスで別のユーザーが登録している場合は復元できない
+    let email_in_use = users::Entity::find()
+        .filter(users::Column::Email.eq(&backup.email))
+        .filter(users::Column::Id.ne(&user.id))
+        .one(&txn)
+        .await?
+        .is_some();
+    if email_in_use {
+        return Err(ApiError::new(
+            StatusCode::CONFLICT,
+            "EMAIL_IN_USE",
+            "Email is already used by another user",
+        ));
+    }
+
+    users::Entity::update_many()
+        .col_expr(users::Column::Email, Expr::value(backup.email))
+        .col_expr(users::Column::Name, Expr::value(backup.name))
+        .col_expr(users::Column::Image, Expr::value(backup.image))
+        .col_expr(
+            users::Column::EmailVerified,
+            Expr::value(backup.email_verified),
+        )
+        .col_expr(
+            users::Column::DeletedAt,
+            Expr::value(Option::<chrono::DateTime<Utc>>::None),
+        )
+        .col_expr(
+            users::Column::UpdatedAt,
+            Expr::value(Utc::now().trunc_subsecs(6)),
+        )
+        .filter(users::Column::Id.eq(&user.id))
+        .exec(&txn)
+        .await?;
+
+    if !backup.accounts.is_empty() {
+        accounts::Entity::insert_many(
+            backup
+                .accounts
+                .into_iter()
+                .map(|account| account.into_active_model().reset_all()),
+        )
+        .exec(&txn)
+        .await?;
+    }
+
+    backup_row.delete(&txn).await?;
+    verifications::Entity::delete_many()
+        .filter(verifications::Column::Identifier.starts_with(RESTORE_IDENTIFIER_PREFIX))
+        .filter(verifications::Column::Value.eq(&user.id))
+        .exec(&txn)
+        .await?;
+
+    txn.commit().await?;
+
+    tracing::info!("User restored: {}", user.id);
+    Ok(())
+}
+
+/// 復元メールの送信依頼のレート制限
+/// カウンタはメモリ上にあり、インスタンスごとに別々に数える（N インスタンスなら上限は実質 N 倍）。
+/// 再起動でもリセットされる
+pub struct RestoreRateLimit {
+    by_email: RateLimiter,
+    by_ip: RateLimiter,
+}
+
+impl Default for RestoreRateLimit {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RestoreRateLimit {
+    pub fn new() -> Self {
+        Self {
+            by_email: RateLimiter::new(RESTORE_REQUESTS_PER_EMAIL, RESTORE_RATE_LIMIT_WINDOW),
+            by_ip: RateLimiter::new(RESTORE_REQUESTS_PER_IP, RESTORE_RATE_LIMIT_WINDOW),
+        }
+    }
+
+    /// メールアドレス・IP の両方が上限以内なら true（どちらの回数も記録する）
+    fn check(&self, email: &str, ip: Option<IpAddr>) -> bool {
+        let email_ok = self.by_email.check(&email.trim().to_lowercase());
+        let ip_ok = ip.is_none_or(|ip| self.by_ip.check(&ip.to_string()));
+        email_ok && ip_ok
+    }
+}
+
+/// 復元用のリンクをメールで送信
+/// 該当するユーザーがいなくても成功として扱う（登録状況を推測されないようにする）
+/// 検索・送信はバックグラウンドで行い、応答時間からも区別できないようにする
+pub async fn request_restore(
+    state: &AppState,
+    email: &str,
+    ip: Option<IpAddr>,
+) -> Result<(), ApiError> {
+    if !state.restore_rate_limit.check(email, ip) {
+        return Err(ApiError::new(
+            StatusCode::TOO_MANY_REQUESTS,
+            "TOO_MANY_REQUESTS",
+            "Too many requests. Please try again later.",
+        ));
+    }
+
+    let state = state.clone();
+    let email = email.trim().to_string();
+    tokio::spawn(async move {
+        if let Err(e) = send_restore_email(&state, &email).await {
+            tracing::error!("Failed to process restore request: {}", e);
+        }
+    });
+    Ok(())
+}
+
+async fn send_restore_email(state: &AppState, email: &str) -> Result<(), DbErr> {
+    let config = &state.auth_config;
+    let cutoff = Utc::now() - config.withdrawal_grace_period;
+
+    // 退会 → 同じメールアドレスで再登録 → 退会の場合は最新のものを復元する
+    let Some(backup_row) = user_withdrawals::Entity::find()
+        .filter(user_withdrawals::Column::EmailHash.eq(email_hash(&config.secret, email)))
+        .filter(user_withdrawals::Column::CreatedAt.gt(cutoff))
+        .order_by_desc(user_withdrawals::Column::CreatedAt)
+        .one(state.db.as_ref())
+        .await?
+    else {
+        return Ok(());
+    };
+
+    let mut token = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut token);
+    let token = hex::encode(token);
+
+    let now = Utc::now().fixed_offset();
+    verifications::ActiveModel {
+        id: Set(uuid::Uuid::new_v4().to_string()),
+        identifier: Set(format!("{RESTORE_IDENTIFIER_PREFIX}{token}")),
+        value: Set(backup_row.user_id.clone()),
+        expires_at: Set(now + chrono::Duration::hours(RESTORE_TOKEN_EXPIRES_IN_HOURS)),
+        created_at: Set(now),
+        updated_at: Set(now),
+    }
+    .insert(state.db.as_ref())
+    .await?;
+
+    let url = format!("{}/restore-account?token={}", config.base_url, token);
+    let html = format!(
+        r#"
+          <h1>アカウントの復元</h1>
+          <p>以下のリンクをクリックすると、退会したアカウントを復元できます。</p>
+          <p><a href="{url}">アカウントを復元する</a></p>
+          <p>このリンクは{RESTORE_TOKEN_EXPIRES_IN_HOURS}時間有効です。心当たりがない場合は、このメールを破棄してください。</p>
+        "#
+    );
+
+    if let Err(e) = state
+        .mailer
+        .send(email, "アカウントの復元", &html)
+        .await
+    {
+        tracing::error!(
+            "Failed to send restore email ({}): {}",
+            backup_row.user_id,
+            e
+        );
+    }
+    Ok(())
+}
+
+/// メールのリンクのトークンを検証して復元
+pub async fn confirm_restore(state: &AppState, token: &str) -> Result<(), ApiError> {
+    let verification = verifications::Entity::find()
+        .filter(verifications::Column::Identifier.eq(format!("{RESTORE_IDENTIFIER_PREFIX}{token}")))
+        .filter(verifications::Column::ExpiresAt.gt(Utc::now()))
+        .one(state.db.as_ref())
+        .await?
+        .ok_or_else(|| {
+            ApiError::new(
+                StatusCode::BAD_REQUEST,
+                "INVALID_RESTORE_TOKEN",
+                "Invalid or expired token",
+            )
+        })?;
+
+    restore(state, &verification.value).await
+}
+
+/// 猶予期間を過ぎた退会ユーザーを定期的に完全削除
+pub fn spawn_purge_job(state: AppState) {
+    let interval = state.auth_config.withdrawal_purge_interval;
+    if interval.is_zero() {
+        tracing::info!("WITHDRAWAL_PURGE_INTERVAL_SECONDS is 0, purge job is disabled");
+        return;
+    }
+
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = purge(&state).await {
+                tracing::error!("Failed to purge withdrawn users: {}", e);
+            }
+        }
+    });
+}
+
+async fn purge(state: &AppState) -> Result<(), DbErr> {
+    let cutoff = Utc::now() - state.auth_config.withdrawal_grace_period;
+
+    let ids: Vec<String> = users::Entity::find()
+        .select_only()
+        .column(users::Column::Id)
+        .filter(users::Column::DeletedAt.lt(cutoff))
+        .into_tuple()
+        .all(state.db.as_ref())
+        .await?;
+    if ids.is_empty() {
+        return Ok(());
+    }
+
+    // sessions / accounts / user_withdrawals は外部キーの ON DELETE CASCADE で削除される
+    let result = users::Entity::delete_many()
+        .filter(users::Column::Id.is_in(ids.clone()))
+        .filter(users::Column::DeletedAt.lt(cutoff))
+        .exec(state.db.as_ref())
+        .await?;
+
+    verifications::Entity::delete_many()
+        .filter(verifications::Column::Identifier.starts_with(RESTORE_IDENTIFIER_PREFIX))
+        .filter(verifications::Column::Value.is_in(ids.clone()))
+        .exec(state.db.as_ref())
+        .await?;
+
+    for id in &ids {
+        for size in avatar::THUMBNAIL_SIZES {
+            if let Err(e) = state.blob_store.delete(&avatar::key(id, size)).await {
+                tracing::warn!("Failed to delete avatar of purged user {}: {}", id, e);
+            }
+        }
+        if let Err(e) = state.exporter.delete(id).await {
+            tracing::warn!("Failed to delete export of purged user {}: {}", id, e);
+        }
+    }
+
+    tracing::info!("Purged {} withdrawn users", result.rows_affected);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::sync::Arc;
+
+    use sea_orm::{DatabaseBackend, MockDatabase, MockExecResult};
+
+    use super::*;
+    use crate::export::Exporter;
+    use crate::storage::{BlobStore, MemoryBlobStore};
+
+    const SECRET: &str = "your-secret-key-at-least-32-characters-long";
+
+    /// purge が読み込む退会ユーザーの ID
+    fn ids(ids: &[&str]) -> Vec<BTreeMap<String, sea_orm::Value>> {
+        ids.iter()
+            .map(|id| BTreeMap::from([("id".to_string(), sea_orm::Value::from(*id))]))
+            .collect()
+    }
+
+    fn state(db: MockDatabase, blob_store: Arc<MemoryBlobStore>) -> AppState {
+        let mut state = AppState::for_tests(db.into_connection(), AuthConfig::for_tests(SECRET));
+        state.exporter = Arc::new(Exporter::from_env(blob_store.clone()));
+        state.blob_store = blob_store;
+        state
+    }
+
+    fn executed_sql(state: AppState) -> Vec<String> {
+        let db = state.db.clone();
+        drop(state);
+        let Ok(db) = Arc::try_unwrap(db) else {
+            panic!("database connection is still in use");
+        };
+        db.into_transaction_log()
+            .iter()
+            .flat_map(|transaction| transaction.statements().to_vec())
+            .map(|statement| statement.sql)
+            .collect()
+    }
+
+    #[test]
+    fn backup_round_trips_only_with_the_same_secret() {
+        let encrypted = encrypted_backup(SECRET, "user@example.com", Vec::new());
+
+        let backup = decrypt(SECRET, &encrypted).unwrap();
+        assert_eq!(backup.email, "user@example.com");
+        assert!(!encrypted.contains("user@example.com"));
+        assert!(decrypt("another-secret-key-at-least-32-characters", &encrypted).is_none());
+        assert!(decrypt(SECRET, "not base64").is_none());
+    }
+
+    #[test]
+    fn email_hash_ignores_case_and_whitespace() {
+        assert_eq!(
+            email_hash(SECRET, " User@Example.com "),
+            email_hash(SECRET, "user@example.com")
+        );
+        assert_ne!(
+            email_hash(SECRET, "user@example.com"),
+            email_hash(SECRET, "other@example.com")
+        );
+    }
+
+    #[tokio::test]
+    async fn purge_deletes_expired_users_and_their_files() {
+        let store = Arc::new(MemoryBlobStore::default());
+        for key in [
+            avatar::key("user-2", 256),
+            avatar::key("user-2", 64),
+            avatar::key("user-3", 256),
+            "private/exports/user-2/export.json".to_string(),
+        ] {
+            store
+                .put(&key, b"data".to_vec(), "image/png")
+                .await
+                .unwrap();
+        }
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([ids(&["user-2"])])
+            .append_exec_results([
+                MockExecResult {
+                    last_insert_id: 0,
+                    rows_affected: 1,
+                },
+                MockExecResult {
+                    last_insert_id: 0,
+                    rows_affected: 1,
+                },
+            ]);
+        let state = state(db, store.clone());
+
+        purge(&state).await.unwrap();
+
+        let mut keys: Vec<_> = store.0.lock().unwrap().keys().cloned().collect();
+        keys.sort();
+        assert_eq!(keys, [avatar::key("user-3", 256)]);
+
+        let sql = executed_sql(state);
+        assert!(
+            sql[0].contains(r#""users"."deleted_at" < $1"#),
+            "{}",
+            sql[0]
+        );
+        // 読み込んだ後に復元されたユーザーは削除しない
+        assert!(
+            sql[1].starts_with(r#"DELETE FROM "users""#)
+                && sql[1].contains(r#""users"."deleted_at" < $"#),
+            "{}",
+            sql[1]
+        );
+        assert!(
+            sql[2].starts_with(r#"DELETE FROM "verifications""#),
+            "{}",
+            sql[2]
+        );
+    }
+
+    #[tokio::test]
+    async fn purge_does_nothing_without_expired_users() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres).append_query_results([ids(&[])]);
+        let state = state(db, Arc::new(MemoryBlobStore::default()));
+
+        purge(&state).await.unwrap();
+
+        let sql = executed_sql(state);
+        assert_eq!(sql.len(), 1);
+        assert!(sql[0].starts_with("SELECT"));
+    }
+}
@@ -33,6 +33,15 @@ export default function LoginPage() {
             パスワードをお忘れの方
           </Link>
         </div>
+
+        <div className="mt-2 text-center">
+          <Link
+            href="/restore-account"
+            className="text-sm font-medium text-blue-600 hover:text-blue-500"
+          >
+            退会したアカウントを復元する
+          </Link>
+        </div>
       </div>
     </>
   );
@@ -0,0 +1,30 @@
+import Link from "next/link";
+import { RestoreAccountForm } from "@/components/auth/RestoreAccountForm";
+
+export default function RestoreAccountPage() {
+  return (
+    <>
+      <div>
+        <h2 className="mt-6 text-center text-3xl font-extrabold text-gray-900">
+          アカウントの復元
+        </h2>
+        <p className="mt-2 text-center text-sm text-gray-600">
+          退会後の猶予期間内であれば、アカウントを復元できます。
+        </p>
+      </div>
+
+      <div className="mt-8 bg-white py-8 px-4 shadow sm:rounded-lg sm:px-10">
+        <RestoreAccountForm />
+
+        <div className="mt-6 text-center">
+          <Link
+            href="/login"
+            className="text-sm font-medium text-blue-600 hover:text-blue-500"
+          >
+            ログインに戻る
+          </Link>
+        </div>
+      </div>
+    </>
+  );
+}
@@ -4,10 +4,19 @@ import { auth } from "@/lib/auth";
 import { WithdrawButton } from "@/components/auth/WithdrawButton";
 
 export default async function SettingsPage() {
+  const requestHeaders = await headers();
   const session = await auth.api.getSession({
-    headers: await headers(),
+    headers: requestHeaders,
   });
 
+  // パスワードでログインできるユーザーは、退会時にパスワードの再入力が必要
+  const accounts = await auth.api.listUserAccounts({
+    headers: requestHeaders,
+  });
+  const hasPassword = accounts.some(
+    (account) => account.providerId === "credential"
+  );
+
   return (
     <div className="min-h-screen bg-gray-50">
       <nav className="bg-white shadow">
@@ -52,7 +61,7 @@ export default async function SettingsPage() {
                 <p className="text-sm text-gray-600 mb-4">
                   退会すると、アカウントは無効化され、同じメールアドレスで再登録できるようになります。
                 </p>
-                <WithdrawButton />
+                <WithdrawButton requiresPassword={hasPassword} />
               </div>
             </div>
           </div>
@@ -0,0 +1,157 @@
+"use client";
+
+import { useState } from "react";
+import { useRouter, useSearchParams } from "next/navigation";
+
+const backendUrl = process.env.NEXT_PUBLIC_BACKEND_URL || "http://localhost:3051";
+
+/**
+ * 退会したアカウントの復元
+ * - token なし: メールアドレスを入力し、復元用のリンクを送信
+ * - token あり（メールのリンク）: 復元を確定
+ */
+export function RestoreAccountForm() {
+  const router = useRouter();
+  const searchParams = useSearchParams();
+  const token = searchParams.get("token");
+
+  const [email, setEmail] = useState("");
+  const [error, setError] = useState<string | null>(null);
+  const [success, setSuccess] = useState(false);
+  const [isLoading, setIsLoading] = useState(false);
+
+  const handleRequest = async (e: React.FormEvent) => {
+    e.preventDefault();
+    setError(null);
+    setIsLoading(true);
+
+    try {
+      const res = await fetch(`${backendUrl}/api/account/restore`, {
+        method: "POST",
+        headers: { "Content-Type": "application/json" },
+        body: JSON.stringify({ email }),
+      });
+
+      if (!res.ok) {
+        setError("送信に失敗しました");
+        return;
+      }
+
+      setSuccess(true);
+    } catch {
+      setError("送信に失敗しました");
+    } finally {
+      setIsLoading(false);
+    }
+  };
+
+  const handleConfirm = async () => {
+    setError(null);
+    setIsLoading(true);
+
+    try {
+      const res = await fetch(`${backendUrl}/api/account/restore/confirm`, {
+        method: "POST",
+        headers: { "Content-Type": "application/json" },
+        body: JSON.stringify({ token }),
+      });
+
+      if (!res.ok) {
+        const data = await res.json().catch(() => null);
+        if (data?.error?.code === "EMAIL_IN_USE") {
+          setError("このメールアドレスは別のアカウントで使用されているため、復元できません");
+        } else {
+          setError("無効または期限切れのリンクです");
+        }
+        return;
+      }
+
+      setSuccess(true);
+      setTimeout(() => {
+        router.push("/login");
+      }, 3000);
+    } catch {
+      setError("アカウントの復元に失敗しました");
+    } finally {
+      setIsLoading(false);
+    }
+  };
+
+  if (success) {
+    return (
+      <div className="text-center">
+        <div className="mb-4 p-4 bg-green-50 border border-green-200 rounded-md">
+          {token ? (
+            <>
+              <p className="text-green-800">アカウントを復元しました。</p>
+              <p className="text-sm text-green-600 mt-2">
+                3秒後にログインページに移動します...
+              </p>
+            </>
+          ) : (
+            <>
+              <p className="text-green-800">
+                復元可能なアカウントがある場合、復元用のメールを送信しました。
+              </p>
+              <p className="text-sm text-green-600 mt-2">
+                メールに記載されたリンクからアカウントを復元してください。
+              </p>
+            </>
+          )}
+        </div>
+      </div>
+    );
+  }
+
+  const errorMessage = error && (
+    <div className="p-3 text-sm text-red-500 bg-red-50 border border-red-200 rounded-md">
+      {error}
+    </div>
+  );
+
+  if (token) {
+    return (
+      <div className="space-y-4">
+        {errorMessage}
+
+        <button
+          type="button"
+          onClick={handleConfirm}
+          disabled={isLoading}
+          className="w-full py-2 px-4 border border-transparent rounded-md shadow-sm text-sm font-medium text-white bg-blue-600 hover:bg-blue-700 focus:outline-none focus:ring-2 focus:ring-offset-2 focus:ring-blue-500 disabled:opacity-50 disabled:cursor-not-allowed"
+        >
+          {isLoading ? "復元中..." : "アカウントを復元"}
+        </button>
+      </div>
+    );
+  }
+
+  return (
+    <form onSubmit={handleRequest} className="space-y-4">
+      {errorMessage}
+
+      <div>
+        <label htmlFor="email" className="block text-sm font-medium text-gray-700">
+          メールアドレス
+        </label>
+        <input
+          id="email"
+          type="email"
+          value={email}
+          onChange={(e) => setEmail(e.target.value)}
+          required
+          className="mt-1 block w-full px-3 py-2 border border-gray-300 rounded-md shadow-sm focus:outline-none focus:ring-blue-500 focus:border-blue-500"
+          placeholder="example@example.com"
+        />
+      </div>
+
+      <button
+        type="submit"
+        disabled={isLoading}
+        className="w-full py-2 px-4 border border-transparent rounded-md shadow-sm text-sm font-medium text-white bg-blue-600 hover:bg-blue-700 focus:outline-none focus:ring-2 focus:ring-offset-2 focus:ring-blue-500 disabled:opacity-50 disabled:cursor-not-allowed"
+      >
+        {isLoading ? "送信中..." : "復元用のメールを送信"}
+      </button>
+    </form>
+  );
+}
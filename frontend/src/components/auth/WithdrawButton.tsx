@@ -3,24 +3,43 @@
 import { useState } from "react";
 import { useRouter } from "next/navigation";
 
-export function WithdrawButton() {
+const backendUrl = process.env.NEXT_PUBLIC_BACKEND_URL || "http://localhost:3051";
+
+type WithdrawButtonProps = {
+  /** メール/パスワードでログインできるユーザーはパスワードの再入力が必要 */
+  requiresPassword: boolean;
+};
+
+/**
+ * 退会ボタン
+ * - Axum の POST /api/user/withdraw を呼ぶ（パスワード確認・単一トランザクション）
+ * - 退会後は猶予期間内であれば /restore-account から復元できる
+ */
+export function WithdrawButton({ requiresPassword }: WithdrawButtonProps) {
   const router = useRouter();
   const [isConfirming, setIsConfirming] = useState(false);
+  const [password, setPassword] = useState("");
   const [isLoading, setIsLoading] = useState(false);
   const [error, setError] = useState<string | null>(null);
 
-  const handleWithdraw = async () => {
+  const handleWithdraw = async (e: React.FormEvent) => {
+    e.preventDefault();
     setIsLoading(true);
     setError(null);
 
     try {
-      const response = await fetch("/api/user/withdraw", {
+      const response = await fetch(`${backendUrl}/api/user/withdraw`, {
         method: "POST",
+        credentials: "include",
+        headers: { "Content-Type": "application/json" },
+        body: JSON.stringify(
+          requiresPassword ? { confirmPassword: password } : {}
+        ),
       });
 
       if (!response.ok) {
-        const data = await response.json();
-        throw new Error(data.error || "退会処理に失敗しました");
+        const data = await response.json().catch(() => null);
+        throw new Error(data?.error?.message || "退会処理に失敗しました");
       }
 
       // 退会成功後、ログインページへリダイレクト
@@ -32,6 +51,12 @@ export function WithdrawButton() {
     }
   };
 
+  const handleCancel = () => {
+    setIsConfirming(false);
+    setPassword("");
+    setError(null);
+  };
+
   if (!isConfirming) {
     return (
       <button
@@ -44,29 +69,52 @@ export function WithdrawButton() {
   }
 
   return (
-    <div className="border border-red-200 rounded-lg p-4 bg-red-50">
+    <form
+      onSubmit={handleWithdraw}
+      className="border border-red-200 rounded-lg p-4 bg-red-50"
+    >
       <p className="text-sm text-red-800 mb-4">
-        本当に退会しますか？この操作は取り消せません。
+        本当に退会しますか？退会後は一定期間内であれば復元できます。
       </p>
+      {requiresPassword && (
+        <div className="mb-4">
+          <label
+            htmlFor="withdraw-password"
+            className="block text-sm font-medium text-gray-700 mb-1"
+          >
+            確認のため、現在のパスワードを入力してください
+          </label>
+          <input
+            id="withdraw-password"
+            type="password"
+            autoComplete="current-password"
+            required
+            value={password}
+            onChange={(e) => setPassword(e.target.value)}
+            className="w-full px-3 py-2 border border-gray-300 rounded-md text-sm focus:outline-none focus:ring-2 focus:ring-red-500"
+          />
+        </div>
+      )}
       {error && (
         <p className="text-sm text-red-600 mb-4">{error}</p>
       )}
       <div className="flex space-x-3">
         <button
-          onClick={handleWithdraw}
-          disabled={isLoading}
+          type="submit"
+          disabled={isLoading || (requiresPassword && !password)}
           className="px-4 py-2 bg-red-600 text-white text-sm rounded-md hover:bg-red-700 disabled:opacity-50 disabled:cursor-not-allowed"
         >
           {isLoading ? "処理中..." : "退会を確定する"}
         </button>
         <button
-          onClick={() => setIsConfirming(false)}
+          type="button"
+          onClick={handleCancel}
           disabled={isLoading}
           className="px-4 py-2 bg-gray-200 text-gray-700 text-sm rounded-md hover:bg-gray-300 disabled:opacity-50"
         >
           キャンセル
         </button>
       </div>
-    </div>
+    </form>
   );
 }
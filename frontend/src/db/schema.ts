@@ -16,6 +16,11 @@ export const user = pgTable("users", {
   createdAt: timestamp("created_at", { withTimezone: true }).defaultNow().notNull(),
   updatedAt: timestamp("updated_at", { withTimezone: true }).defaultNow().notNull(),
   deletedAt: timestamp("deleted_at", { withTimezone: true }),
+  // admin プラグイン互換（カンマ区切りで複数ロール）
+  role: text("role").default("user"),
+  banned: boolean("banned").default(false),
+  banReason: text("ban_reason"),
+  banExpires: timestamp("ban_expires", { withTimezone: true }),
 });
 
 // session テーブル（Better Auth は単数形を期待）
@@ -30,6 +35,8 @@ export const session = pgTable("sessions", {
   userAgent: text("user_agent"),
   createdAt: timestamp("created_at", { withTimezone: true }).defaultNow().notNull(),
   updatedAt: timestamp("updated_at", { withTimezone: true }).defaultNow().notNull(),
+  // admin プラグイン互換（なりすましを開始した管理者のユーザー ID）
+  impersonatedBy: text("impersonated_by"),
 });
 
 // account テーブル（Better Auth は単数形を期待）